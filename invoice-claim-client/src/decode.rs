@@ -0,0 +1,27 @@
+//! Typed decoding of `invoice_claim` accounts and events from raw RPC data.
+
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator, Event};
+
+pub use anchor_lang::error::Error;
+
+/// True when `data` starts with the Anchor discriminator of `T`.
+pub fn is_account<T: Discriminator>(data: &[u8]) -> bool {
+    data.starts_with(T::DISCRIMINATOR)
+}
+
+/// Decodes an account of type `T`, checking its discriminator first.
+pub fn account<T: AccountDeserialize>(data: &[u8]) -> Result<T, Error> {
+    T::try_deserialize(&mut &data[..])
+}
+
+/// Anchor discriminator of `T`, e.g. for `getProgramAccounts` memcmp filters.
+pub fn discriminator<T: Discriminator>() -> &'static [u8] {
+    T::DISCRIMINATOR
+}
+
+/// Decodes an event of type `T` from the base64-decoded payload of a
+/// `Program data:` log line; `None` if the payload is a different event.
+pub fn event<T: Event + AnchorDeserialize>(data: &[u8]) -> Option<T> {
+    let body = data.strip_prefix(T::DISCRIMINATOR)?;
+    T::try_from_slice(body).ok()
+}
@@ -0,0 +1,514 @@
+//! Builders for every `invoice_claim` instruction.
+//!
+//! Account lists are produced by the program's generated `accounts::*` structs and
+//! data by its `instruction::*` structs, so order and encoding always match on-chain.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use invoice_claim::{accounts, instruction, AuditReason, RejectReason, UpdateOrgConfigArgs, ID};
+
+use crate::pda;
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// `invoice_id` must be the org's current `OrgConfig.invoice_counter`; `amount` is in
+/// base units of `OrgConfig.mint`.
+pub fn request_invoice_extraction(
+    authority: &Pubkey,
+    org_config: &Pubkey,
+    invoice_id: u64,
+    ipfs_hash: String,
+    amount: u64,
+) -> Instruction {
+    build(
+        accounts::RequestExtraction {
+            org_config: *org_config,
+            invoice_request: pda::invoice_request(org_config, invoice_id).0,
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::RequestInvoiceExtraction { ipfs_hash, amount },
+    )
+}
+
+/// Oracle submission of OCR results for request `invoice_id` of `org_config`;
+/// `ipfs_hash` is the request's document, used to derive its duplicate registry.
+/// Below the org's `min_confidence_bps`, `confidence_bps` sends the invoice to audit.
+#[allow(clippy::too_many_arguments)]
+pub fn process_extraction_result(
+    oracle: &Pubkey,
+    org_config: &Pubkey,
+    invoice_id: u64,
+    ipfs_hash: &str,
+    vendor_name: String,
+    amount: u64,
+    due_date: i64,
+    invoice_number: String,
+    confidence_bps: u16,
+) -> Instruction {
+    let vendor_account = pda::vendor(org_config, &vendor_name).0;
+    build(
+        accounts::ProcessResult {
+            payer: *oracle,
+            org_config: *org_config,
+            vendor_account,
+            invoice_request: pda::invoice_request(org_config, invoice_id).0,
+            invoice_account: pda::invoice(org_config, invoice_id).0,
+            document_registry: pda::document_registry(org_config, ipfs_hash).0,
+            fingerprint_registry: pda::invoice_fingerprint_registry(org_config, &vendor_account, &invoice_number, amount).0,
+            system_program: system_program::ID,
+        },
+        instruction::ProcessExtractionResult { vendor_name, amount, due_date, invoice_number, confidence_bps },
+    )
+}
+
+/// Oracle marks request `invoice_id` of `org_config` as Failed for `reason`.
+pub fn reject_extraction(oracle: &Pubkey, org_config: &Pubkey, invoice_id: u64, reason: RejectReason) -> Instruction {
+    build(
+        accounts::RejectExtraction {
+            oracle: *oracle,
+            org_config: *org_config,
+            invoice_request: pda::invoice_request(org_config, invoice_id).0,
+        },
+        instruction::RejectExtraction { reason },
+    )
+}
+
+pub fn request_invoice_audit_vrf(
+    payer: &Pubkey,
+    org_config: &Pubkey,
+    invoice: &Pubkey,
+    client_seed: u8,
+) -> Instruction {
+    build(
+        accounts::RequestInvoiceAuditVrf {
+            payer: *payer,
+            org_config: *org_config,
+            invoice_account: *invoice,
+            oracle_queue: ephemeral_vrf_sdk::consts::DEFAULT_QUEUE,
+            program_identity: pda::vrf_program_identity().0,
+            vrf_program: ephemeral_vrf_sdk::consts::VRF_PROGRAM_ID,
+            slot_hashes: sysvar::slot_hashes::ID,
+            system_program: system_program::ID,
+        },
+        instruction::RequestInvoiceAuditVrf { client_seed },
+    )
+}
+
+/// Normally sent by the VRF program itself; exposed for local testing.
+pub fn callback_invoice_vrf(invoice: &Pubkey, org_config: &Pubkey, randomness: [u8; 32]) -> Instruction {
+    build(
+        accounts::CallbackInvoiceVrf {
+            vrf_program_identity: ephemeral_vrf_sdk::consts::VRF_PROGRAM_IDENTITY,
+            invoice_account: *invoice,
+            org_config: *org_config,
+        },
+        instruction::CallbackInvoiceVrf { randomness },
+    )
+}
+
+pub fn process_invoice_payment(authority: &Pubkey, org_config: &Pubkey, invoice: &Pubkey) -> Instruction {
+    build(
+        accounts::ProcessPayment {
+            invoice_account: *invoice,
+            org_config: *org_config,
+            authority: *authority,
+        },
+        instruction::ProcessInvoicePayment {},
+    )
+}
+
+pub fn complete_payment(authority: &Pubkey, invoice: &Pubkey, vendor: &Pubkey) -> Instruction {
+    build(
+        accounts::CompletePayment {
+            invoice_account: *invoice,
+            vendor_account: *vendor,
+            authority: *authority,
+        },
+        instruction::CompletePayment {},
+    )
+}
+
+pub fn cancel_invoice(authority: &Pubkey, invoice: &Pubkey) -> Instruction {
+    build(
+        accounts::CancelInvoice {
+            invoice_account: *invoice,
+            authority: *authority,
+        },
+        instruction::CancelInvoice {},
+    )
+}
+
+/// Returns the escrow balance to `funder_ata`, which must be owned by whoever funded it.
+pub fn refund_escrow(
+    authority: &Pubkey,
+    org_config: &Pubkey,
+    invoice: &Pubkey,
+    funder_ata: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::RefundEscrow {
+            org_config: *org_config,
+            invoice_account: *invoice,
+            escrow_authority: pda::escrow_authority(invoice).0,
+            escrow_ata: pda::escrow_token_account(invoice, mint),
+            funder_ata: *funder_ata,
+            mint: *mint,
+            token_program: anchor_spl::token::ID,
+            authority: *authority,
+        },
+        instruction::RefundEscrow {},
+    )
+}
+
+/// `mint` is the org's `OrgConfig.mint`, used to find the escrow account to close.
+/// `leftover_ata` receives any balance still in the escrow and must be owned by
+/// `InvoiceAccount::escrow_recipient`; it may be `None` when the escrow is empty.
+pub fn close_invoice(
+    authority: &Pubkey,
+    org_config: &Pubkey,
+    invoice: &Pubkey,
+    mint: &Pubkey,
+    leftover_ata: Option<Pubkey>,
+) -> Instruction {
+    build(
+        accounts::CloseInvoice {
+            invoice_account: *invoice,
+            org_config: *org_config,
+            escrow_authority: pda::escrow_authority(invoice).0,
+            escrow_ata: pda::escrow_token_account(invoice, mint),
+            leftover_ata,
+            token_program: anchor_spl::token::ID,
+            authority: *authority,
+        },
+        instruction::CloseInvoice {},
+    )
+}
+
+pub fn close_request(authority: &Pubkey, invoice_request: &Pubkey) -> Instruction {
+    build(
+        accounts::CloseRequest {
+            invoice_request: *invoice_request,
+            authority: *authority,
+        },
+        instruction::CloseRequest {},
+    )
+}
+
+/// `evidence_hash` is an IPFS CID or hex digest of the reviewer's supporting evidence.
+pub fn audit_decide(
+    reviewer: &Pubkey,
+    org_config: &Pubkey,
+    invoice: &Pubkey,
+    approve: bool,
+    reason: AuditReason,
+    evidence_hash: String,
+) -> Instruction {
+    build(
+        accounts::AuditDecide {
+            reviewer: *reviewer,
+            org_config: *org_config,
+            invoice_account: *invoice,
+            audit_record: pda::audit_record(invoice).0,
+            system_program: system_program::ID,
+        },
+        instruction::AuditDecide { approve, reason, evidence_hash },
+    )
+}
+
+pub fn org_init(
+    authority: &Pubkey,
+    treasury_vault: Pubkey,
+    mint: Pubkey,
+    per_invoice_cap: u64,
+    daily_cap: u64,
+    audit_rate_bps: u16,
+) -> Instruction {
+    build(
+        accounts::OrgInit {
+            org_config: pda::org_config(authority).0,
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::OrgInit {
+            treasury_vault,
+            mint,
+            per_invoice_cap,
+            daily_cap,
+            audit_rate_bps,
+        },
+    )
+}
+
+/// Grows `authority`'s pre-auditor org to the current `OrgConfig` layout; the authority
+/// pays the extra rent.
+pub fn migrate_org_config(authority: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateOrgConfig {
+            org_config: pda::org_config(authority).0,
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateOrgConfig {},
+    )
+}
+
+/// Read-only; simulate it and read the u64 return data.
+pub fn get_daily_headroom(org_config: &Pubkey) -> Instruction {
+    build(
+        accounts::DailyHeadroom { org_config: *org_config },
+        instruction::GetDailyHeadroom {},
+    )
+}
+
+pub fn update_org_config(authority: &Pubkey, update_args: UpdateOrgConfigArgs) -> Instruction {
+    build(update_accounts(authority), instruction::UpdateOrgConfig { update_args })
+}
+
+fn update_accounts(authority: &Pubkey) -> accounts::UpdateOrgConfig {
+    accounts::UpdateOrgConfig {
+        authority: *authority,
+        org_config: pda::org_config(authority).0,
+    }
+}
+
+pub fn add_auditor(authority: &Pubkey, auditor: Pubkey) -> Instruction {
+    build(update_accounts(authority), instruction::AddAuditor { auditor })
+}
+
+pub fn remove_auditor(authority: &Pubkey, auditor: Pubkey) -> Instruction {
+    build(update_accounts(authority), instruction::RemoveAuditor { auditor })
+}
+
+/// Moves `invoice.amount` from `payer_ata` into the invoice's escrow token account,
+/// which must already exist as the escrow authority's associated token account.
+pub fn fund_escrow(
+    payer: &Pubkey,
+    invoice_authority: &Pubkey,
+    org_config: &Pubkey,
+    invoice: &Pubkey,
+    payer_ata: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::FundEscrow {
+            org_config: *org_config,
+            invoice_account: *invoice,
+            escrow_authority: pda::escrow_authority(invoice).0,
+            payer: *payer,
+            authority: *invoice_authority,
+            payer_ata: *payer_ata,
+            escrow_ata: pda::escrow_token_account(invoice, mint),
+            mint: *mint,
+            token_program: anchor_spl::token::ID,
+        },
+        instruction::FundEscrow {},
+    )
+}
+
+/// Pays the escrow out to `vendor_ata`, which must be owned by `vendor`'s registered wallet.
+pub fn settle_to_vendor(
+    invoice_authority: &Pubkey,
+    org_config: &Pubkey,
+    invoice: &Pubkey,
+    vendor: &Pubkey,
+    vendor_ata: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::SettleToVendor {
+            org_config: *org_config,
+            invoice_account: *invoice,
+            escrow_authority: pda::escrow_authority(invoice).0,
+            vendor_account: *vendor,
+            vendor_ata: *vendor_ata,
+            escrow_ata: pda::escrow_token_account(invoice, mint),
+            mint: *mint,
+            token_program: anchor_spl::token::ID,
+            authority: *invoice_authority,
+        },
+        instruction::SettleToVendor {},
+    )
+}
+
+pub fn register_vendor(authority: &Pubkey, vendor_name: String, wallet: Pubkey) -> Instruction {
+    let org_config = pda::org_config(authority).0;
+    build(
+        accounts::RegisterVendor {
+            vendor_account: pda::vendor(&org_config, &vendor_name).0,
+            org_config,
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterVendor { vendor_name, wallet },
+    )
+}
+
+/// `None` when `vendor_name` is too long to ever have had a legacy address.
+pub fn migrate_vendor(authority: &Pubkey, vendor_name: String) -> Option<Instruction> {
+    let org_config = pda::org_config(authority).0;
+    Some(build(
+        accounts::MigrateVendor {
+            legacy_vendor_account: pda::legacy_vendor(&org_config, &vendor_name)?.0,
+            vendor_account: pda::vendor(&org_config, &vendor_name).0,
+            org_config,
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateVendor { vendor_name },
+    ))
+}
+
+/// Points `invoice`, extracted for `vendor_name`, at the vendor's migrated account.
+pub fn migrate_invoice_vendor(authority: &Pubkey, invoice: &Pubkey, vendor_name: &str) -> Instruction {
+    let org_config = pda::org_config(authority).0;
+    build(
+        accounts::MigrateInvoiceVendor {
+            invoice_account: *invoice,
+            vendor_account: pda::vendor(&org_config, vendor_name).0,
+            org_config,
+            authority: *authority,
+        },
+        instruction::MigrateInvoiceVendor {},
+    )
+}
+
+fn manage_vendor(authority: &Pubkey, vendor_name: &str) -> accounts::ManageVendor {
+    let org_config = pda::org_config(authority).0;
+    accounts::ManageVendor {
+        vendor_account: pda::vendor(&org_config, vendor_name).0,
+        org_config,
+        authority: *authority,
+    }
+}
+
+pub fn deactivate_vendor(authority: &Pubkey, vendor_name: &str) -> Instruction {
+    build(manage_vendor(authority, vendor_name), instruction::DeactivateVendor {})
+}
+
+pub fn activate_vendor(authority: &Pubkey, vendor_name: &str) -> Instruction {
+    build(manage_vendor(authority, vendor_name), instruction::ActivateVendor {})
+}
+
+pub fn update_vendor_wallet(authority: &Pubkey, vendor_name: &str, new_wallet: Pubkey) -> Instruction {
+    build(
+        manage_vendor(authority, vendor_name),
+        instruction::UpdateVendorWallet { new_wallet },
+    )
+}
+
+pub fn get_vendor_period_paid(vendor: &Pubkey) -> Instruction {
+    build(
+        accounts::VendorPeriodPaid { vendor_account: *vendor },
+        instruction::GetVendorPeriodPaid {},
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::instruction::AccountMeta;
+    use anchor_lang::Discriminator;
+
+    // Expected metas are listed in the order of the fields of the program's Accounts struct
+
+    #[test]
+    fn process_extraction_result_follows_process_result() {
+        let (oracle, org) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (vendor_name, invoice_number) = ("Acme Corp".to_string(), "INV-1".to_string());
+        let ix = process_extraction_result(
+            &oracle, &org, 7, "bafkreiexample", vendor_name, 1_000, 1_800_000_000, invoice_number, 9_000,
+        );
+
+        let vendor = pda::vendor(&org, "Acme Corp").0;
+        assert_eq!(
+            ix.accounts,
+            [
+                AccountMeta::new(oracle, true),
+                AccountMeta::new(org, false),
+                AccountMeta::new_readonly(vendor, false),
+                AccountMeta::new(pda::invoice_request(&org, 7).0, false),
+                AccountMeta::new(pda::invoice(&org, 7).0, false),
+                AccountMeta::new(pda::document_registry(&org, "bafkreiexample").0, false),
+                AccountMeta::new(pda::invoice_fingerprint_registry(&org, &vendor, "INV-1", 1_000).0, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+            ]
+        );
+        assert!(ix.data.starts_with(instruction::ProcessExtractionResult::DISCRIMINATOR));
+    }
+
+    #[test]
+    fn settle_to_vendor_follows_settle_to_vendor() {
+        let (authority, org, invoice) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (vendor, vendor_ata, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = settle_to_vendor(&authority, &org, &invoice, &vendor, &vendor_ata, &mint);
+
+        assert_eq!(
+            ix.accounts,
+            [
+                AccountMeta::new_readonly(org, false),
+                AccountMeta::new(invoice, false),
+                AccountMeta::new_readonly(pda::escrow_authority(&invoice).0, false),
+                AccountMeta::new(vendor, false),
+                AccountMeta::new(vendor_ata, false),
+                AccountMeta::new(pda::escrow_token_account(&invoice, &mint), false),
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new_readonly(anchor_spl::token::ID, false),
+                AccountMeta::new_readonly(authority, true),
+            ]
+        );
+        assert!(ix.data.starts_with(instruction::SettleToVendor::DISCRIMINATOR));
+    }
+
+    #[test]
+    fn close_invoice_follows_close_invoice() {
+        let (authority, org, invoice) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (mint, leftover_ata) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let metas = |leftover: AccountMeta| {
+            vec![
+                AccountMeta::new(invoice, false),
+                AccountMeta::new_readonly(org, false),
+                AccountMeta::new_readonly(pda::escrow_authority(&invoice).0, false),
+                AccountMeta::new(pda::escrow_token_account(&invoice, &mint), false),
+                leftover,
+                AccountMeta::new_readonly(anchor_spl::token::ID, false),
+                AccountMeta::new(authority, true),
+            ]
+        };
+
+        let ix = close_invoice(&authority, &org, &invoice, &mint, Some(leftover_ata));
+        assert_eq!(ix.accounts, metas(AccountMeta::new(leftover_ata, false)));
+        // Anchor passes the program id for an omitted optional account
+        let ix = close_invoice(&authority, &org, &invoice, &mint, None);
+        assert_eq!(ix.accounts, metas(AccountMeta::new_readonly(ID, false)));
+    }
+
+    #[test]
+    fn migrate_vendor_follows_migrate_vendor() {
+        let authority = Pubkey::new_unique();
+        let org = pda::org_config(&authority).0;
+        let ix = migrate_vendor(&authority, "Acme Corp".to_string()).unwrap();
+
+        assert_eq!(
+            ix.accounts,
+            [
+                AccountMeta::new(pda::legacy_vendor(&org, "Acme Corp").unwrap().0, false),
+                AccountMeta::new(pda::vendor(&org, "Acme Corp").0, false),
+                AccountMeta::new_readonly(org, false),
+                AccountMeta::new(authority, true),
+                AccountMeta::new_readonly(system_program::ID, false),
+            ]
+        );
+        assert!(migrate_vendor(&authority, "x".repeat(33)).is_none());
+    }
+}
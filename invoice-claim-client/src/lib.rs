@@ -0,0 +1,20 @@
+//! Off-chain client for the `invoice_claim` program.
+//!
+//! Account types, instruction arguments and account lists come straight from the
+//! program crate, so decoders and builders here cannot drift from what is deployed.
+
+pub mod decode;
+pub mod instructions;
+pub mod pda;
+
+pub use invoice_claim::events;
+pub use invoice_claim::{
+    AuditReason, AuditRecord, InvoiceAccount, InvoiceError, InvoiceRegistry, InvoiceRequest,
+    InvoiceStatus, OrgConfig, RejectReason, RequestStatus, UpdateOrgConfigArgs, VendorAccount, ID,
+};
+
+/// Anchor's framework errors, e.g. `AccountNotInitialized` for a PDA that was never created.
+pub use anchor_lang::error::ErrorCode as AnchorErrorCode;
+
+/// SPL Token or Token-2022 mint, e.g. for `OrgConfig.mint` decimals.
+pub use anchor_spl::token_interface::Mint;
@@ -0,0 +1,110 @@
+//! Program-derived addresses for every seed scheme used by `invoice_claim`.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address;
+use invoice_claim::{document_hash, invoice_fingerprint, vendor_seed, ID};
+
+/// `[b"org_config", authority]`
+pub fn org_config(authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"org_config", authority.as_ref()], &ID)
+}
+
+/// `[b"request", org_config, invoice_id (u64 LE)]`
+pub fn invoice_request(org_config: &Pubkey, invoice_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"request", org_config.as_ref(), &invoice_id.to_le_bytes()], &ID)
+}
+
+/// `[b"invoice", org_config, invoice_id (u64 LE)]`
+pub fn invoice(org_config: &Pubkey, invoice_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"invoice", org_config.as_ref(), &invoice_id.to_le_bytes()], &ID)
+}
+
+/// `[b"vendor", org_config, sha256(normalized vendor_name)]`; case and spacing don't matter
+pub fn vendor(org_config: &Pubkey, vendor_name: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vendor", org_config.as_ref(), &vendor_seed(vendor_name)], &ID)
+}
+
+/// `[b"vendor", org_config, vendor_name]`, the pre-hashing address `migrate_vendor` moves from.
+/// Only derivable for names of at most 32 bytes.
+pub fn legacy_vendor(org_config: &Pubkey, vendor_name: &str) -> Option<(Pubkey, u8)> {
+    Pubkey::try_find_program_address(&[b"vendor", org_config.as_ref(), vendor_name.as_bytes()], &ID)
+}
+
+/// `[b"escrow_auth", invoice]`, the signer over an invoice's escrow token account
+pub fn escrow_authority(invoice: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"escrow_auth", invoice.as_ref()], &ID)
+}
+
+/// `[b"doc_hash", org_config, sha256(ipfs_hash)]`, claimed once a document became an invoice
+pub fn document_registry(org_config: &Pubkey, ipfs_hash: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"doc_hash", org_config.as_ref(), &document_hash(ipfs_hash)], &ID)
+}
+
+/// `[b"fingerprint", org_config, sha256(vendor, invoice_number, amount)]`, claimed once the
+/// vendor's invoice number was paid at that amount
+pub fn invoice_fingerprint_registry(
+    org_config: &Pubkey,
+    vendor: &Pubkey,
+    invoice_number: &str,
+    amount: u64,
+) -> (Pubkey, u8) {
+    let fingerprint = invoice_fingerprint(vendor, invoice_number, amount);
+    Pubkey::find_program_address(&[b"fingerprint", org_config.as_ref(), &fingerprint], &ID)
+}
+
+/// `[b"audit", invoice]`, the decision record of an audited invoice
+pub fn audit_record(invoice: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"audit", invoice.as_ref()], &ID)
+}
+
+/// Associated token account of the invoice's escrow authority for `mint`
+pub fn escrow_token_account(invoice: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(&escrow_authority(invoice).0, mint)
+}
+
+/// `[b"identity"]`, the program identity the VRF program checks on randomness requests
+pub fn vrf_program_identity() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ephemeral_vrf_sdk::consts::IDENTITY], &ID)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::hash::hash;
+
+    #[test]
+    fn invoice_ids_are_seeded_as_little_endian_u64() {
+        let org = Pubkey::new_unique();
+        // 258 = 0x0102
+        let seed = [0x02, 0x01, 0, 0, 0, 0, 0, 0];
+        let request = Pubkey::find_program_address(&[b"request", org.as_ref(), &seed], &ID);
+        let invoice_pda = Pubkey::find_program_address(&[b"invoice", org.as_ref(), &seed], &ID);
+
+        assert_eq!(invoice_request(&org, 258), request);
+        assert_eq!(invoice(&org, 258), invoice_pda);
+        assert_ne!(invoice(&org, 258).0, invoice(&Pubkey::new_unique(), 258).0);
+    }
+
+    #[test]
+    fn vendors_are_seeded_by_the_hash_of_the_normalized_name() {
+        let org = Pubkey::new_unique();
+        let seed = hash(b"acme corp").to_bytes();
+        let expected = Pubkey::find_program_address(&[b"vendor", org.as_ref(), &seed], &ID);
+
+        assert_eq!(vendor(&org, "Acme Corp"), expected);
+        assert_eq!(vendor(&org, "  ACME   corp "), expected);
+        // Names past the 32-byte seed limit still derive
+        vendor(&org, &"x".repeat(50));
+    }
+
+    #[test]
+    fn legacy_vendors_are_seeded_by_the_raw_name() {
+        let org = Pubkey::new_unique();
+        let expected = Pubkey::find_program_address(&[b"vendor", org.as_ref(), b"Acme Corp"], &ID);
+
+        assert_eq!(legacy_vendor(&org, "Acme Corp"), Some(expected));
+        assert_ne!(legacy_vendor(&org, "acme corp"), Some(expected));
+        assert_ne!(expected, vendor(&org, "Acme Corp"));
+        assert_eq!(legacy_vendor(&org, &"x".repeat(33)), None);
+    }
+}
@@ -0,0 +1,618 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Deserializer};
+use solana_sdk::pubkey::Pubkey;
+
+const DEFAULT_CONFIG_PATH: &str = "oracle.toml";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Cluster {
+    Localnet,
+    #[default]
+    Devnet,
+    Mainnet,
+}
+
+impl Cluster {
+    fn rpc_url(self) -> &'static str {
+        match self {
+            Cluster::Localnet => "http://127.0.0.1:8899",
+            Cluster::Devnet => "https://api.devnet.solana.com",
+            Cluster::Mainnet => "https://api.mainnet-beta.solana.com",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OcrProviderKind {
+    #[default]
+    Ocrspace,
+    Tesseract,
+    Fixture,
+}
+
+/// Flags that override both the config file and the environment
+#[derive(Args, Debug, Default)]
+pub struct ConfigArgs {
+    /// TOML config file (default: $ORACLE_CONFIG, then ./oracle.toml if present)
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    /// Cluster whose default RPC/websocket endpoints to use
+    #[arg(long, global = true)]
+    pub cluster: Option<Cluster>,
+    #[arg(long, global = true)]
+    pub rpc_url: Option<String>,
+    #[arg(long, global = true)]
+    pub ws_url: Option<String>,
+    /// Oracle signer keypair file
+    #[arg(long, global = true)]
+    pub keypair: Option<PathBuf>,
+    #[arg(long, global = true)]
+    pub job_store: Option<PathBuf>,
+    #[arg(long, global = true)]
+    pub ocr_provider: Option<OcrProviderKind>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OcrConfig {
+    pub provider: OcrProviderKind,
+    pub language: String,
+    /// IPFS gateway documents are fetched from, e.g. a Pinata dedicated gateway
+    pub ipfs_gateway: String,
+    pub api_key: Option<String>,
+    pub record_dir: Option<PathBuf>,
+    pub fixture_dir: Option<PathBuf>,
+    pub tesseract_bin: String,
+}
+
+impl Default for OcrConfig {
+    fn default() -> Self {
+        OcrConfig {
+            provider: OcrProviderKind::default(),
+            language: "eng".to_string(),
+            ipfs_gateway: "https://emerald-abundant-baboon-978.mypinata.cloud/ipfs".to_string(),
+            api_key: None,
+            record_dir: None,
+            fixture_dir: None,
+            tesseract_bin: "tesseract".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryConfig {
+    pub max_attempts: u32,
+    pub base_delay_secs: u64,
+    pub max_delay_secs: u64,
+    pub jitter: f64,
+    /// How often the job store is checked for failed requests whose backoff has elapsed
+    pub tick_secs: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            max_attempts: 5,
+            base_delay_secs: 10,
+            max_delay_secs: 600,
+            jitter: 0.2,
+            tick_secs: 5,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VendorMatchConfig {
+    /// Minimum name similarity (0.0..=1.0) to submit without manual review
+    pub threshold: f64,
+}
+
+impl Default for VendorMatchConfig {
+    fn default() -> Self {
+        VendorMatchConfig { threshold: 0.85 }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VrfConfig {
+    /// Request VRF audit randomness right after each validated invoice
+    pub auto_request: bool,
+}
+
+/// Settings one org overrides under `[orgs.<org_config address>]`; anything unset
+/// falls back to the global `[ocr]` and `[vrf]` sections
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OrgOverrides {
+    pub ocr_provider: Option<OcrProviderKind>,
+    pub ocr_language: Option<String>,
+    pub ipfs_gateway: Option<String>,
+    pub auto_request_vrf: Option<bool>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub cluster: Cluster,
+    /// Empty means the cluster default
+    pub rpc_url: String,
+    /// Empty means derived from `rpc_url`
+    pub ws_url: String,
+    pub keypair_path: PathBuf,
+    /// Interval of the reconciling getProgramAccounts poll behind the websocket feed
+    pub reconcile_interval_secs: u64,
+    pub job_store_path: PathBuf,
+    pub ocr: OcrConfig,
+    pub retry: RetryConfig,
+    pub vendor_match: VendorMatchConfig,
+    pub vrf: VrfConfig,
+    /// Per-org overrides; every org naming this oracle as `oracle_signer` is served either way
+    #[serde(deserialize_with = "de_orgs")]
+    pub orgs: BTreeMap<Pubkey, OrgOverrides>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            cluster: Cluster::default(),
+            rpc_url: String::new(),
+            ws_url: String::new(),
+            keypair_path: PathBuf::from("oracle-keypair.json"),
+            reconcile_interval_secs: 60,
+            job_store_path: PathBuf::from("oracle-jobs.db"),
+            ocr: OcrConfig::default(),
+            retry: RetryConfig::default(),
+            vendor_match: VendorMatchConfig::default(),
+            vrf: VrfConfig::default(),
+            orgs: BTreeMap::new(),
+        }
+    }
+}
+
+fn de_orgs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<Pubkey, OrgOverrides>, D::Error> {
+    BTreeMap::<String, OrgOverrides>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(org, overrides)| {
+            Pubkey::from_str(&org)
+                .map(|org| (org, overrides))
+                .map_err(|e| serde::de::Error::custom(format!("Invalid org '{}': {}", org, e)))
+        })
+        .collect()
+}
+
+fn env_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+fn parse_env<T: FromStr>(
+    env: &impl Fn(&str) -> Option<String>,
+    name: &str,
+) -> Result<Option<T>, Box<dyn std::error::Error>>
+where
+    T::Err: std::fmt::Display,
+{
+    match env(name) {
+        Some(v) => v
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("Invalid {}='{}': {}", name, v, e).into()),
+        None => Ok(None),
+    }
+}
+
+fn parse_bool(name: &str, value: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" => Ok(true),
+        "0" | "false" => Ok(false),
+        _ => Err(format!("Invalid {}='{}': expected true, false, 1 or 0", name, value).into()),
+    }
+}
+
+impl Config {
+    /// Defaults, then the TOML file, then environment variables, then CLI flags.
+    pub fn load(args: &ConfigArgs) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = args
+            .config
+            .clone()
+            .or_else(|| env_var("ORACLE_CONFIG").map(PathBuf::from));
+
+        let mut config = match &path {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => Self::from_file(Path::new(DEFAULT_CONFIG_PATH))?,
+            None => Config::default(),
+        };
+
+        config.apply_env(env_var)?;
+        config.apply_args(args)?;
+        config.resolve_endpoints();
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn from_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let raw = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read config {}: {}", path.display(), e))?;
+        toml::from_str(&raw).map_err(|e| format!("Invalid config {}: {}", path.display(), e).into())
+    }
+
+    /// `env` is the process environment outside tests, see `env_var`.
+    fn apply_env(&mut self, env: impl Fn(&str) -> Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(v) = env("CLUSTER") {
+            self.cluster = Cluster::from_str(&v, true).map_err(|e| format!("Invalid CLUSTER: {}", e))?;
+        }
+        if let Some(v) = env("RPC_URL") {
+            self.rpc_url = v;
+        }
+        if let Some(v) = env("WS_URL") {
+            self.ws_url = v;
+        }
+        if let Some(v) = env("KEYPAIR_PATH") {
+            self.keypair_path = PathBuf::from(v);
+        }
+        if let Some(v) = parse_env(&env, "RECONCILE_INTERVAL_SECS")? {
+            self.reconcile_interval_secs = v;
+        }
+        if let Some(v) = env("JOB_STORE_PATH") {
+            self.job_store_path = PathBuf::from(v);
+        }
+
+        if let Some(v) = env("OCR_PROVIDER") {
+            self.ocr.provider = OcrProviderKind::from_str(&v, true)
+                .map_err(|e| format!("Invalid OCR_PROVIDER: {}", e))?;
+        }
+        if let Some(v) = env("OCR_LANGUAGE") {
+            self.ocr.language = v;
+        }
+        if let Some(v) = env("IPFS_GATEWAY") {
+            self.ocr.ipfs_gateway = v;
+        }
+        if let Some(v) = env("OCR_API_KEY") {
+            self.ocr.api_key = Some(v);
+        }
+        if let Some(v) = env("OCR_RECORD_DIR") {
+            self.ocr.record_dir = Some(PathBuf::from(v));
+        }
+        if let Some(v) = env("OCR_FIXTURE_DIR") {
+            self.ocr.fixture_dir = Some(PathBuf::from(v));
+        }
+        if let Some(v) = env("TESSERACT_BIN") {
+            self.ocr.tesseract_bin = v;
+        }
+
+        if let Some(v) = parse_env(&env, "RETRY_MAX_ATTEMPTS")? {
+            self.retry.max_attempts = v;
+        }
+        if let Some(v) = parse_env(&env, "RETRY_BASE_DELAY_SECS")? {
+            self.retry.base_delay_secs = v;
+        }
+        if let Some(v) = parse_env(&env, "RETRY_MAX_DELAY_SECS")? {
+            self.retry.max_delay_secs = v;
+        }
+        if let Some(v) = parse_env(&env, "RETRY_JITTER")? {
+            self.retry.jitter = v;
+        }
+
+        if let Some(v) = parse_env(&env, "VENDOR_MATCH_THRESHOLD")? {
+            self.vendor_match.threshold = v;
+        }
+
+        if let Some(v) = env("AUTO_REQUEST_VRF") {
+            self.vrf.auto_request = parse_bool("AUTO_REQUEST_VRF", &v)?;
+        }
+        Ok(())
+    }
+
+    fn apply_args(&mut self, args: &ConfigArgs) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(cluster) = args.cluster {
+            self.cluster = cluster;
+        }
+        if let Some(v) = &args.rpc_url {
+            self.rpc_url = v.clone();
+        }
+        if let Some(v) = &args.ws_url {
+            self.ws_url = v.clone();
+        }
+        if let Some(v) = &args.keypair {
+            self.keypair_path = v.clone();
+        }
+        if let Some(v) = &args.job_store {
+            self.job_store_path = v.clone();
+        }
+        if let Some(v) = args.ocr_provider {
+            self.ocr.provider = v;
+        }
+        Ok(())
+    }
+
+    fn resolve_endpoints(&mut self) {
+        if self.rpc_url.is_empty() {
+            self.rpc_url = self.cluster.rpc_url().to_string();
+        }
+        if self.ws_url.is_empty() {
+            // Solana serves pubsub one port above JSON-RPC on a local validator
+            self.ws_url = self
+                .rpc_url
+                .replacen("https://", "wss://", 1)
+                .replacen("http://", "ws://", 1)
+                .replace(":8899", ":8900");
+        }
+    }
+
+    fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.rpc_url.starts_with("http://") && !self.rpc_url.starts_with("https://") {
+            return Err(format!("rpc_url must be http(s): {}", self.rpc_url).into());
+        }
+        if !self.ws_url.starts_with("ws://") && !self.ws_url.starts_with("wss://") {
+            return Err(format!("ws_url must be ws(s): {}", self.ws_url).into());
+        }
+        if self.reconcile_interval_secs == 0 {
+            return Err("reconcile_interval_secs must be greater than 0".into());
+        }
+
+        if self.ocr.language.is_empty() {
+            return Err("ocr.language must not be empty".into());
+        }
+        if !self.ocr.ipfs_gateway.starts_with("http://") && !self.ocr.ipfs_gateway.starts_with("https://") {
+            return Err(format!("ocr.ipfs_gateway must be http(s): {}", self.ocr.ipfs_gateway).into());
+        }
+        for (org, overrides) in &self.orgs {
+            if overrides.ocr_language.as_ref().is_some_and(|l| l.is_empty()) {
+                return Err(format!("orgs.{}.ocr_language must not be empty", org).into());
+            }
+            if let Some(gateway) = &overrides.ipfs_gateway
+                && !gateway.starts_with("http://")
+                && !gateway.starts_with("https://")
+            {
+                return Err(format!("orgs.{}.ipfs_gateway must be http(s): {}", org, gateway).into());
+            }
+        }
+
+        if self.retry.max_attempts == 0 {
+            return Err("retry.max_attempts must be at least 1".into());
+        }
+        if self.retry.base_delay_secs > self.retry.max_delay_secs {
+            return Err("retry.base_delay_secs must not exceed retry.max_delay_secs".into());
+        }
+        if !(0.0..=1.0).contains(&self.retry.jitter) {
+            return Err("retry.jitter must be between 0 and 1".into());
+        }
+        if self.retry.tick_secs == 0 {
+            return Err("retry.tick_secs must be greater than 0".into());
+        }
+
+        if !(0.0..=1.0).contains(&self.vendor_match.threshold) {
+            return Err("vendor_match.threshold must be between 0 and 1".into());
+        }
+
+        Ok(())
+    }
+
+    /// Checks only the `run` command needs; operator commands work without them.
+    pub fn validate_for_run(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.keypair_path.exists() {
+            return Err(format!("Keypair file not found: {}", self.keypair_path.display()).into());
+        }
+
+        let providers = std::iter::once(self.ocr.provider)
+            .chain(self.orgs.values().filter_map(|o| o.ocr_provider));
+        for provider in providers {
+            match provider {
+                OcrProviderKind::Ocrspace if self.ocr.api_key.is_none() => {
+                    return Err("ocr.api_key (OCR_API_KEY) is required for the ocrspace provider".into());
+                }
+                OcrProviderKind::Fixture if self.ocr.fixture_dir.is_none() => {
+                    return Err("ocr.fixture_dir (OCR_FIXTURE_DIR) is required for the fixture provider".into());
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The `[ocr]` section with `org`'s overrides applied.
+    pub fn ocr_for(&self, org: &Pubkey) -> OcrConfig {
+        let mut ocr = self.ocr.clone();
+        if let Some(overrides) = self.orgs.get(org) {
+            if let Some(provider) = overrides.ocr_provider {
+                ocr.provider = provider;
+            }
+            if let Some(language) = &overrides.ocr_language {
+                ocr.language = language.clone();
+            }
+            if let Some(gateway) = &overrides.ipfs_gateway {
+                ocr.ipfs_gateway = gateway.clone();
+            }
+        }
+        ocr
+    }
+
+    pub fn auto_request_vrf_for(&self, org: &Pubkey) -> bool {
+        self.orgs
+            .get(org)
+            .and_then(|o| o.auto_request_vrf)
+            .unwrap_or(self.vrf.auto_request)
+    }
+
+    pub fn reconcile_interval(&self) -> Duration {
+        Duration::from_secs(self.reconcile_interval_secs)
+    }
+
+    pub fn retry_tick(&self) -> Duration {
+        Duration::from_secs(self.retry.tick_secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env_of(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: BTreeMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    fn resolved(mut config: Config) -> Result<Config, Box<dyn std::error::Error>> {
+        config.resolve_endpoints();
+        config.validate()?;
+        Ok(config)
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        let config = resolved(Config::default()).unwrap();
+        assert_eq!(config.rpc_url, "https://api.devnet.solana.com");
+        assert_eq!(config.ws_url, "wss://api.devnet.solana.com");
+    }
+
+    #[test]
+    fn local_ws_url_is_one_port_above_rpc() {
+        let config = resolved(Config { cluster: Cluster::Localnet, ..Config::default() }).unwrap();
+        assert_eq!(config.ws_url, "ws://127.0.0.1:8900");
+    }
+
+    #[test]
+    fn file_then_env_then_flags() {
+        let mut config: Config = toml::from_str(
+            r#"
+            cluster = "localnet"
+            reconcile_interval_secs = 30
+            job_store_path = "file.db"
+
+            [ocr]
+            provider = "tesseract"
+            language = "deu"
+
+            [retry]
+            max_attempts = 3
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.retry.base_delay_secs, RetryConfig::default().base_delay_secs);
+
+        config
+            .apply_env(env_of(&[
+                ("CLUSTER", "mainnet"),
+                ("RECONCILE_INTERVAL_SECS", "15"),
+                ("JOB_STORE_PATH", "env.db"),
+                ("OCR_PROVIDER", "fixture"),
+            ]))
+            .unwrap();
+        config
+            .apply_args(&ConfigArgs {
+                job_store: Some(PathBuf::from("flag.db")),
+                ocr_provider: Some(OcrProviderKind::Ocrspace),
+                ..ConfigArgs::default()
+            })
+            .unwrap();
+        let config = resolved(config).unwrap();
+
+        assert_eq!(config.cluster, Cluster::Mainnet);
+        assert_eq!(config.rpc_url, "https://api.mainnet-beta.solana.com");
+        assert_eq!(config.reconcile_interval_secs, 15);
+        assert_eq!(config.job_store_path, PathBuf::from("flag.db"));
+        assert_eq!(config.ocr.provider, OcrProviderKind::Ocrspace);
+        assert_eq!(config.ocr.language, "deu");
+        assert_eq!(config.retry.max_attempts, 3);
+    }
+
+    #[test]
+    fn auto_request_vrf_must_be_a_bool() {
+        for (value, expected) in [("1", true), ("TRUE", true), ("0", false), ("false", false)] {
+            let mut config = Config::default();
+            config.apply_env(env_of(&[("AUTO_REQUEST_VRF", value)])).unwrap();
+            assert_eq!(config.vrf.auto_request, expected, "{}", value);
+        }
+
+        let err = Config::default()
+            .apply_env(env_of(&[("AUTO_REQUEST_VRF", "yes")]))
+            .unwrap_err();
+        assert!(err.to_string().contains("AUTO_REQUEST_VRF='yes'"), "{}", err);
+    }
+
+    #[test]
+    fn invalid_env_numbers_are_rejected() {
+        let err = Config::default()
+            .apply_env(env_of(&[("RETRY_MAX_ATTEMPTS", "five")]))
+            .unwrap_err();
+        assert!(err.to_string().contains("RETRY_MAX_ATTEMPTS='five'"), "{}", err);
+    }
+
+    #[test]
+    fn program_id_is_not_configurable() {
+        let err = toml::from_str::<Config>(r#"program_id = "11111111111111111111111111111111""#).unwrap_err();
+        assert!(err.to_string().contains("program_id"), "{}", err);
+    }
+
+    #[test]
+    fn org_overrides_fall_back_to_globals() {
+        let org = Pubkey::new_unique();
+        let config: Config = toml::from_str(&format!(
+            r#"
+            [vrf]
+            auto_request = true
+
+            [orgs.{}]
+            ocr_language = "fra"
+            auto_request_vrf = false
+            "#,
+            org
+        ))
+        .unwrap();
+
+        assert_eq!(config.ocr_for(&org).language, "fra");
+        assert_eq!(config.ocr_for(&org).provider, OcrProviderKind::Ocrspace);
+        assert!(!config.auto_request_vrf_for(&org));
+        assert_eq!(config.ocr_for(&Pubkey::new_unique()).language, "eng");
+        assert!(config.auto_request_vrf_for(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn validation_rejects_out_of_range_settings() {
+        type Breakage = fn(&mut Config);
+        let cases: [(&str, Breakage); 7] = [
+            ("rpc_url must be http(s)", |c| c.rpc_url = "ftp://example.com".to_string()),
+            ("reconcile_interval_secs", |c| c.reconcile_interval_secs = 0),
+            ("ocr.language", |c| c.ocr.language.clear()),
+            ("retry.max_attempts", |c| c.retry.max_attempts = 0),
+            ("retry.base_delay_secs", |c| c.retry.base_delay_secs = c.retry.max_delay_secs + 1),
+            ("retry.jitter", |c| c.retry.jitter = 1.5),
+            ("vendor_match.threshold", |c| c.vendor_match.threshold = -0.1),
+        ];
+        for (message, break_config) in cases {
+            let mut config = Config::default();
+            break_config(&mut config);
+            let err = resolved(config).unwrap_err();
+            assert!(err.to_string().contains(message), "{}: {}", message, err);
+        }
+    }
+
+    #[test]
+    fn run_requires_provider_settings() {
+        let mut config = resolved(Config::default()).unwrap();
+        config.keypair_path = PathBuf::from("Cargo.toml");
+        let err = config.validate_for_run().unwrap_err();
+        assert!(err.to_string().contains("ocr.api_key"), "{}", err);
+
+        config.ocr.api_key = Some("key".to_string());
+        config.validate_for_run().unwrap();
+
+        config.orgs.insert(
+            Pubkey::new_unique(),
+            OrgOverrides { ocr_provider: Some(OcrProviderKind::Fixture), ..OrgOverrides::default() },
+        );
+        let err = config.validate_for_run().unwrap_err();
+        assert!(err.to_string().contains("ocr.fixture_dir"), "{}", err);
+    }
+}
@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use invoice_claim_client::{decode, InvoiceRequest, OrgConfig, VendorAccount};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Delay before re-opening a dropped websocket subscription
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// Raw InvoiceRequest account data keyed by its address
+pub type AccountUpdate = (Pubkey, Vec<u8>);
+
+// Only InvoiceRequest accounts, so OrgConfig/VendorAccount/InvoiceAccount never leave the RPC node
+fn invoice_request_config() -> RpcProgramAccountsConfig {
+    RpcProgramAccountsConfig {
+        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            0,
+            decode::discriminator::<InvoiceRequest>(),
+        ))]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// Reconciling fetch of every InvoiceRequest account owned by the program.
+pub fn fetch_invoice_requests(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+) -> Result<Vec<AccountUpdate>, Box<dyn std::error::Error>> {
+    let accounts = rpc_client.get_program_accounts_with_config(program_id, invoice_request_config())?;
+    Ok(accounts.into_iter().map(|(pubkey, account)| (pubkey, account.data)).collect())
+}
+
+/// Every VendorAccount registered under `org_config`, matched on the discriminator and
+/// the `org` field that directly follows it.
+pub fn fetch_org_vendors(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    org_config: &Pubkey,
+) -> Result<Vec<VendorAccount>, Box<dyn std::error::Error>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, decode::discriminator::<VendorAccount>())),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(8, org_config.as_ref())),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let accounts = rpc_client.get_program_accounts_with_config(program_id, config)?;
+    let mut vendors = Vec::with_capacity(accounts.len());
+    for (_, account) in accounts {
+        vendors.push(decode::account::<VendorAccount>(&account.data)?);
+    }
+    Ok(vendors)
+}
+
+/// Every OrgConfig naming `oracle_signer` as its oracle, matched on the discriminator and
+/// the `oracle_signer` field that follows the 32-byte `authority`.
+pub fn fetch_oracle_orgs(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    oracle_signer: &Pubkey,
+) -> Result<Vec<(Pubkey, OrgConfig)>, Box<dyn std::error::Error>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, decode::discriminator::<OrgConfig>())),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(8 + 32, oracle_signer.as_ref())),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let accounts = rpc_client.get_program_accounts_with_config(program_id, config)?;
+    let mut orgs = Vec::with_capacity(accounts.len());
+    for (pubkey, account) in accounts {
+        orgs.push((pubkey, decode::account::<OrgConfig>(&account.data)?));
+    }
+    Ok(orgs)
+}
+
+/// Streams InvoiceRequest account updates over `programSubscribe` into `tx`,
+/// reconnecting whenever the websocket drops. Ends once the receiver is gone.
+pub fn spawn_subscription(ws_url: String, program_id: Pubkey, tx: UnboundedSender<AccountUpdate>) {
+    tokio::spawn(async move {
+        loop {
+            match subscribe_once(&ws_url, &program_id, &tx).await {
+                Ok(()) => println!("Program subscription closed, reconnecting..."),
+                Err(e) => eprintln!("Program subscription error: {}", e),
+            }
+
+            if tx.is_closed() {
+                return;
+            }
+            tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+        }
+    });
+}
+
+async fn subscribe_once(
+    ws_url: &str,
+    program_id: &Pubkey,
+    tx: &UnboundedSender<AccountUpdate>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let client = PubsubClient::new(ws_url).await?;
+    let (mut stream, unsubscribe) = client
+        .program_subscribe(program_id, Some(invoice_request_config()))
+        .await?;
+    println!("Subscribed to InvoiceRequest accounts via {}", ws_url);
+
+    while let Some(update) = stream.next().await {
+        let Ok(pubkey) = update.value.pubkey.parse::<Pubkey>() else {
+            continue;
+        };
+        let Some(data) = update.value.account.data.decode() else {
+            eprintln!("Could not decode account data for {}", pubkey);
+            continue;
+        };
+        if tx.send((pubkey, data)).is_err() {
+            break;
+        }
+    }
+
+    unsubscribe().await;
+    Ok(())
+}
@@ -1,483 +1,857 @@
-use std::env;
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
-use solana_sdk::system_program;
-use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature, Signer};
 use solana_sdk::transaction::Transaction;
-use regex::Regex;
 use solana_client::rpc_client::RpcClient;
+use std::collections::HashMap;
 use std::str::FromStr;
-use std::time::Duration;
 use dotenvy::dotenv;
-use sha2::{Digest, Sha256};
-
-#[derive(Clone, Debug)]
-pub struct InvoiceRequest {
-    pub authority: Pubkey,
-    pub ipfs_hash: String,
-    pub status: RequestStatus,
-    pub timestamp: i64,
-    pub amount: u64,
+use tokio::sync::mpsc;
+use invoice_claim_client::{decode, instructions, pda, InvoiceRegistry, InvoiceRequest, Mint, OrgConfig, RequestStatus};
+
+mod config;
+mod discovery;
+mod ocr;
+mod matching;
+mod parser;
+mod retry;
+mod store;
+
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+use config::{Config, ConfigArgs};
+use ocr::{OcrError, OcrProvider};
+use matching::VendorMatch;
+use parser::{DueDate, Evidence};
+use retry::{ErrorClass, RetryPolicy};
+use store::{FieldEvidence, Job, JobStage, JobStore, ParsedInvoice, Rejection};
+
+#[derive(Parser)]
+#[command(about = "OCR oracle for the invoice_claim program")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    config: ConfigArgs,
 }
 
-impl InvoiceRequest {
-    pub fn from_account_data(data: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
-        if data.len() < 8 {
-            return Err("Data too short".into());
-        }
+#[derive(Subcommand)]
+enum Command {
+    /// Watch for invoice requests and process them (default)
+    Run,
+    /// List requests that exhausted their retries or failed permanently
+    DeadLetters,
+    /// Move a dead-lettered request back into the retry queue
+    Requeue {
+        /// InvoiceRequest account pubkey
+        request: String,
+    },
+    /// List requests whose vendor or due date needs an operator decision
+    Reviews,
+    /// Resolve a request awaiting review and submit it
+    Approve {
+        /// InvoiceRequest account pubkey
+        request: String,
+        /// Exact `vendor_name` of the org's VendorAccount
+        #[arg(long)]
+        vendor: Option<String>,
+        /// Due date as YYYY-MM-DD (payable until the end of that day, UTC)
+        #[arg(long)]
+        due_date: Option<NaiveDate>,
+        /// Invoice number as printed on the document
+        #[arg(long)]
+        invoice_number: Option<String>,
+    },
+    /// Mark a request awaiting review or dead-lettered as Failed on-chain
+    Reject {
+        /// InvoiceRequest account pubkey
+        request: String,
+        #[arg(long, value_enum)]
+        reason: Rejection,
+    },
+    /// Per-org job counts and recent throughput
+    Stats {
+        /// Window for the recent counters
+        #[arg(long, default_value_t = 24)]
+        hours: i64,
+    },
+}
+
+/// An org naming this oracle as its `oracle_signer`, with its effective settings
+struct Tenant {
+    authority: Pubkey,
+    ocr: Box<dyn OcrProvider>,
+    auto_request_vrf: bool,
+}
 
-        let mut offset = 8;
+/// Long-lived handles shared by every request handler
+struct Oracle {
+    rpc_client: RpcClient,
+    keypair: Keypair,
+    /// Keyed by OrgConfig address; refreshed on every reconcile
+    tenants: HashMap<Pubkey, Tenant>,
+    store: JobStore,
+    retry: RetryPolicy,
+    config: Config,
+}
+
+fn open_store(config: &Config) -> JobStore {
+    println!("Job store: {}", config.job_store_path.display());
+    // sled holds an exclusive lock, so operator commands need the oracle stopped
+    JobStore::open(&config.job_store_path).expect("Failed to open job store (is the oracle still running?)")
+}
 
-        // Read authority (32 bytes)
-        if offset + 32 > data.len() {
-            return Err("Not enough data for authority".into());
+#[tokio::main]
+async fn main() {
+    dotenv().ok();
+    let cli = Cli::parse();
+    let config = match Config::load(&cli.config) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Configuration error: {}", e);
+            std::process::exit(1);
         }
-        let authority = Pubkey::try_from(&data[offset..offset + 32])?;
-        offset += 32;
+    };
 
-        // Read string length (4 bytes)
-        if offset + 4 > data.len() {
-            return Err("Not enough data for string length".into());
+    match cli.command.unwrap_or(Command::Run) {
+        Command::Run => run(config).await,
+        Command::DeadLetters => {
+            let store = open_store(&config);
+            let jobs = store.in_stage(JobStage::DeadLettered).expect("Failed to read job store");
+            if jobs.is_empty() {
+                println!("No dead-lettered requests");
+            }
+            for job in jobs {
+                println!(
+                    "{}  org={}  attempts={}  updated={}  error={}",
+                    job.request,
+                    job.org,
+                    job.attempts,
+                    job.updated_at,
+                    job.last_error.as_deref().unwrap_or("-")
+                );
+            }
         }
-        let str_len = u32::from_le_bytes([
-            data[offset], data[offset + 1], data[offset + 2], data[offset + 3]
-        ]) as usize;
-        offset += 4;
-
-        // Read string data
-        if offset + str_len > data.len() {
-            return Err("Not enough data for string".into());
+        Command::Requeue { request } => {
+            let request = Pubkey::from_str(&request).expect("Invalid request pubkey");
+            let store = open_store(&config);
+            let requeued = store.requeue(&request).expect("Failed to requeue");
+            println!("Requeued {} job(s) for {}", requeued, request);
         }
-        let ipfs_hash = String::from_utf8(data[offset..offset + str_len].to_vec())?;
-        offset += str_len;
-
-        // Read status (1 byte)
-        if offset >= data.len() {
-            return Err("Not enough data for status".into());
+        Command::Reviews => {
+            let store = open_store(&config);
+            let jobs = store.in_stage(JobStage::NeedsReview).expect("Failed to read job store");
+            if jobs.is_empty() {
+                println!("No requests awaiting review");
+            }
+            for job in jobs {
+                let candidates = job.parsed.map(|p| p.vendor_candidates).unwrap_or_default();
+                println!(
+                    "{}  org={}  candidates={:?}  reason={}",
+                    job.request,
+                    job.org,
+                    candidates,
+                    job.last_error.as_deref().unwrap_or("-")
+                );
+            }
         }
-        let status = if data[offset] == 0 {
-            RequestStatus::Pending
-        } else {
-            RequestStatus::Completed
-        };
-        offset += 1;
-
-        // Read timestamp (8 bytes)
-        if offset + 8 > data.len() {
-            return Err("Not enough data for timestamp".into());
+        Command::Approve { request, vendor, due_date, invoice_number } => {
+            if vendor.is_none() && due_date.is_none() && invoice_number.is_none() {
+                eprintln!("Nothing to approve: pass --vendor, --due-date and/or --invoice-number");
+                std::process::exit(1);
+            }
+            let request = Pubkey::from_str(&request).expect("Invalid request pubkey");
+            let store = open_store(&config);
+            let approved = store
+                .approve(&request, vendor.as_deref(), due_date.map(parser::end_of_day), invoice_number.as_deref())
+                .expect("Failed to approve");
+            println!("Approved {} job(s) for {}", approved, request);
         }
-        let timestamp = i64::from_le_bytes([
-            data[offset], data[offset + 1], data[offset + 2], data[offset + 3],
-            data[offset + 4], data[offset + 5], data[offset + 6], data[offset + 7]
-        ]);
-        offset +=8;
-
-        // Read amount (8 bytes)
-        if offset + 8 > data.len() {
-            return Err("Not enough data for amount".into());
+        Command::Reject { request, reason } => {
+            let request = Pubkey::from_str(&request).expect("Invalid request pubkey");
+            let store = open_store(&config);
+            let scheduled = store.schedule_rejection(&request, reason).expect("Failed to schedule rejection");
+            println!("Scheduled rejection ({:?}) of {} job(s) for {}; the running oracle submits it", reason, scheduled, request);
+        }
+        Command::Stats { hours } => {
+            let store = open_store(&config);
+            let since = chrono::Utc::now().timestamp() - hours * 3600;
+            let stats = store.org_stats(since).expect("Failed to read job store");
+            if stats.is_empty() {
+                println!("No jobs recorded");
+            }
+            for (org, s) in stats {
+                println!(
+                    "{}  last {}h: {} confirmed, {} rejected  total: confirmed={} rejected={} review={} dead={} in_flight={} amount_mismatch={} low_confidence={}",
+                    org, hours, s.recent_confirmed, s.recent_rejected,
+                    s.confirmed, s.rejected, s.needs_review, s.dead_lettered, s.in_flight, s.amount_mismatches, s.low_confidence
+                );
+            }
         }
-        let amount = u64::from_le_bytes([
-            data[offset], data[offset + 1], data[offset + 2], data[offset + 3],
-            data[offset + 4], data[offset + 5], data[offset + 6], data[offset + 7]
-        ]);
-
-        Ok(InvoiceRequest {
-            authority,
-            ipfs_hash,
-            status,
-            timestamp,
-            amount
-        })
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum RequestStatus {
-    Pending,
-    Completed,
-}
-
-const PROGRAM_ID: &str = "CwD9tU4A7c7SS5b55ZtTcEPGA8svJQUhfdCbdoaSF1Tx";
-const RPC_URL: &str = "https://api.devnet.solana.com";
-
-#[tokio::main]
-async fn main() {
+async fn run(config: Config) {
     println!("Invoice Oracle Backend Starting...");
-    dotenv().ok();
-    let keypair = read_keypair_file("oracle-keypair.json") //Add your keypair
+    if let Err(e) = config.validate_for_run() {
+        eprintln!("Configuration error: {}", e);
+        std::process::exit(1);
+    }
+    println!("Cluster: {:?} ({})", config.cluster, config.rpc_url);
+
+    let keypair = read_keypair_file(&config.keypair_path)
         .expect("Failed to read keypair file");
 
     println!("Oracle wallet: {}", keypair.pubkey());
 
-    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
-    let rpc_client = RpcClient::new(RPC_URL.to_string());
+    let retry = RetryPolicy::from_config(&config.retry);
+    println!("Retry policy: {:?}", retry);
+
+    let ws_url = config.ws_url.clone();
+    let reconcile_interval = config.reconcile_interval();
+    let retry_tick = config.retry_tick();
+    let mut oracle = Oracle {
+        rpc_client: RpcClient::new(config.rpc_url.clone()),
+        keypair,
+        tenants: HashMap::new(),
+        store: open_store(&config),
+        retry,
+        config,
+    };
+    if let Err(e) = refresh_tenants(&mut oracle) {
+        eprintln!("Org discovery failed: {}", e);
+    }
+
+    println!("Watching program: {}", invoice_claim_client::ID);
+    println!("Subscribing via {}, reconciling every {}s...\n", ws_url, reconcile_interval.as_secs());
 
-    println!("Watching program: {}", program_id);
-    println!("Polling every 5 seconds...\n");
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    discovery::spawn_subscription(ws_url, invoice_claim_client::ID, tx);
 
+    let mut reconcile = tokio::time::interval(reconcile_interval);
+    let mut retries = tokio::time::interval(retry_tick);
     let mut poll_count = 0;
 
     loop {
-        poll_count += 1;
-        println!("Poll #{} - Checking for new requests...", poll_count);
-
-        match process_pending_requests(&rpc_client, &keypair, &program_id).await {
-            Ok(processed) => {
-                if processed > 0 {
-                    println!("Processed {} requests", processed);
-                } else {
-                    println!("No pending requests found");
+        tokio::select! {
+            Some((pubkey, data)) = rx.recv() => {
+                println!("Account update: {}", pubkey);
+
+                match handle_request_account(&oracle, &pubkey, &data).await {
+                    Ok(true) => println!("Successfully processed!"),
+                    Ok(false) => {}
+                    Err(e) => eprintln!("Failed: {}", e),
                 }
             }
-            Err(e) => {
-                eprintln!("Error: {}", e);
+            _ = reconcile.tick() => {
+                poll_count += 1;
+                println!("Reconcile #{} - Checking for pending requests...", poll_count);
+
+                // Picks up orgs that switched oracle_signer to or away from us
+                if let Err(e) = refresh_tenants(&mut oracle) {
+                    eprintln!("Org discovery failed: {}", e);
+                }
+
+                match process_pending_requests(&oracle).await {
+                    Ok(processed) => {
+                        if processed > 0 {
+                            println!("Processed {} requests", processed);
+                        } else {
+                            println!("No pending requests found");
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                    }
+                }
+                if let Err(e) = report_throughput(&oracle) {
+                    eprintln!("Stats error: {}", e);
+                }
+            }
+            _ = retries.tick() => {
+                if let Err(e) = retry_due_jobs(&oracle).await {
+                    eprintln!("Retry error: {}", e);
+                }
             }
         }
+    }
+}
+
+/// Syncs `oracle.tenants` with the orgs whose `oracle_signer` is this oracle's keypair,
+/// building each new org's OCR provider from its overrides.
+fn refresh_tenants(oracle: &mut Oracle) -> Result<(), Box<dyn std::error::Error>> {
+    let orgs = discovery::fetch_oracle_orgs(&oracle.rpc_client, &invoice_claim_client::ID, &oracle.keypair.pubkey())?;
+
+    let current: Vec<Pubkey> = orgs.iter().map(|(org, _)| *org).collect();
+    oracle.tenants.retain(|org, _| {
+        let keep = current.contains(org);
+        if !keep {
+            println!("No longer the oracle for org {}", org);
+        }
+        keep
+    });
 
-        tokio::time::sleep(Duration::from_secs(5)).await;
+    for (org, org_config) in orgs {
+        if oracle.tenants.contains_key(&org) {
+            continue;
+        }
+        let ocr = ocr::provider_from_config(&oracle.config.ocr_for(&org))?;
+        let auto_request_vrf = oracle.config.auto_request_vrf_for(&org);
+        println!(
+            "Serving org {} (authority {}): OCR {}, auto VRF {}",
+            org,
+            org_config.authority,
+            ocr.name(),
+            auto_request_vrf
+        );
+        oracle.tenants.insert(org, Tenant { authority: org_config.authority, ocr, auto_request_vrf });
     }
+
+    if oracle.tenants.is_empty() {
+        println!("No OrgConfig names {} as oracle_signer yet", oracle.keypair.pubkey());
+    }
+    Ok(())
 }
 
-async fn process_pending_requests(
-    rpc_client: &RpcClient,
-    keypair: &Keypair,
-    program_id: &Pubkey,
-) -> Result<usize, Box<dyn std::error::Error>> {
-    println!("Fetching program accounts...");
-    // We probably need more filtration here
-    let accounts = rpc_client.get_program_accounts(program_id)?;
+/// Logs each served org's confirmed and rejected requests over the last day.
+fn report_throughput(oracle: &Oracle) -> Result<(), Box<dyn std::error::Error>> {
+    let since = chrono::Utc::now().timestamp() - 24 * 3600;
+    let stats = oracle.store.org_stats(since)?;
+    for (org, tenant) in &oracle.tenants {
+        let s = stats.get(&org.to_string()).copied().unwrap_or_default();
+        println!(
+            "Org {} (authority {}): {} confirmed, {} rejected in 24h; {} in flight, {} awaiting review",
+            org, tenant.authority, s.recent_confirmed, s.recent_rejected, s.in_flight, s.needs_review
+        );
+    }
+    Ok(())
+}
 
-    println!("Found {} total accounts for this program", accounts.len());
+async fn process_pending_requests(oracle: &Oracle) -> Result<usize, Box<dyn std::error::Error>> {
+    let accounts = discovery::fetch_invoice_requests(&oracle.rpc_client, &invoice_claim_client::ID)?;
+    println!("Found {} InvoiceRequest accounts", accounts.len());
 
     let mut processed = 0;
 
-    // Precompute Anchor account discriminator for InvoiceRequest
-    let mut h = Sha256::new();
-    h.update(b"account:InvoiceRequest");
-    let invoice_request_disc: [u8; 8] = h.finalize()[..8].try_into().unwrap();
+    for (pubkey, data) in accounts {
+        match handle_request_account(oracle, &pubkey, &data).await {
+            Ok(true) => {
+                println!("Successfully processed!");
+                processed += 1;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                eprintln!("Failed: {}", e);
+            }
+        }
+    }
 
-    for (pubkey, account) in accounts {
-        println!("Checking account: {}", pubkey);
-        println!("Data length: {} bytes", account.data.len());
+    Ok(processed)
+}
 
-        if account.data.len() < 8 {
-            println!("Account too small, skipping");
-            continue;
-        }
+// Re-fetches each failed request whose backoff elapsed and runs it again
+async fn retry_due_jobs(oracle: &Oracle) -> Result<(), Box<dyn std::error::Error>> {
+    let now = chrono::Utc::now().timestamp();
 
-        let disc = &account.data[..8];
-        println!("Discriminator: {:?}", disc);
+    for mut job in oracle.store.due_retries(now)? {
+        let pubkey = Pubkey::from_str(&job.request)?;
+        let account = oracle
+            .rpc_client
+            .get_account_with_commitment(&pubkey, oracle.rpc_client.commitment())?
+            .value;
 
-        // Only consider InvoiceRequest accounts
-        if disc != &invoice_request_disc {
-            println!("Not an InvoiceRequest account, skipping");
+        let Some(account) = account else {
+            oracle.store.dead_letter(&mut job, "request account no longer exists")?;
+            continue;
+        };
+        if let Ok(request) = decode::account::<InvoiceRequest>(&account.data)
+            && request.timestamp != job.request_timestamp
+        {
+            oracle.store.dead_letter(&mut job, "request account was re-created")?;
             continue;
         }
 
-        match InvoiceRequest::from_account_data(&account.data) {
-            Ok(request) => {
-                println!("Successfully deserialized");
-                println!("Authority: {}", request.authority);
-                println!("IPFS: {}", request.ipfs_hash);
-                println!("Status: {:?}", request.status);
+        println!("Retrying request {} (attempt #{})", pubkey, job.attempts + 1);
+        match handle_request_account(oracle, &pubkey, &account.data).await {
+            Ok(true) => println!("Successfully processed!"),
+            Ok(false) => {}
+            Err(e) => eprintln!("Failed: {}", e),
+        }
+    }
 
-                if matches!(request.status, RequestStatus::Pending) {
-                    println!("\nFound PENDING request!");
+    Ok(())
+}
 
-                    match extract_and_submit(rpc_client, keypair, program_id, &request, &pubkey).await {
-                        Ok(_) => {
-                            println!("Successfully processed!");
-                            processed += 1;
-                        }
-                        Err(e) => {
-                            eprintln!("Failed: {}", e);
-                        }
-                    }
-                } else {
-                    println!("Already completed, skipping");
-                }
-            }
-            Err(e) => {
-                println!("Failed to deserialize InvoiceRequest: {}", e);
-            }
-        }
+// Returns Ok(true) when a pending request was extracted and submitted
+async fn handle_request_account(
+    oracle: &Oracle,
+    pubkey: &Pubkey,
+    data: &[u8],
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if !decode::is_account::<InvoiceRequest>(data) {
+        return Ok(false);
     }
 
-    // Additionally check the org authority's request PDA when scanning (useful in development/testing).
-    let org_authority_str = env::var("ORG_AUTHORITY_PUBKEY").unwrap_or_default();
-    if !org_authority_str.is_empty() {
-        if let Ok(org_auth) = Pubkey::from_str(&org_authority_str) {
-            let (req_pda, _) = Pubkey::find_program_address(&[b"request", org_auth.as_ref()], program_id);
-            if let Ok(acc) = rpc_client.get_account(&req_pda) {
-                if acc.data.len() >= 8 {
-                    let disc = &acc.data[..8];
-                    // Expected discriminator (debug)
-                    let mut h2 = Sha256::new();
-                    h2.update(b"account:InvoiceRequest");
-                    let expected: [u8; 8] = h2.finalize()[..8].try_into().unwrap();
-                    println!("Direct PDA check: {} disc={:?} expected={:?}", req_pda, disc, expected);
-
-                    if disc == &expected {
-                        match InvoiceRequest::from_account_data(&acc.data) {
-                            Ok(request) => {
-                                if matches!(request.status, RequestStatus::Pending) {
-                                    println!("Found PENDING request via direct PDA: {}", req_pda);
-                                    extract_and_submit(rpc_client, keypair, program_id, &request, &req_pda).await?;
-                                    processed += 1;
-                                } else {
-                                    println!("Direct PDA request is not pending (status {:?})", request.status);
-                                }
-                            }
-                            Err(e) => println!("Failed to decode direct PDA request: {}", e),
-                        }
-                    }
-                }
+    let request: InvoiceRequest = decode::account(data)?;
+    if !matches!(request.status, RequestStatus::Pending) {
+        return Ok(false);
+    }
+
+    // Only orgs that name us as oracle_signer; the program would refuse anything else
+    let Some(tenant) = oracle.tenants.get(&request.org) else {
+        return Ok(false);
+    };
+
+    if let Some(job) = oracle.store.get(pubkey, request.timestamp)? {
+        let now = chrono::Utc::now().timestamp();
+        match job.stage {
+            // Stale update for a request we already landed; the chain just hasn't caught up
+            JobStage::Confirmed => {
+                println!("Request {} already confirmed, skipping", pubkey);
+                return Ok(false);
+            }
+            JobStage::DeadLettered => {
+                println!("Request {} is dead-lettered, skipping (requeue to retry)", pubkey);
+                return Ok(false);
+            }
+            JobStage::NeedsReview => {
+                println!("Request {} awaits vendor review, skipping (approve to submit)", pubkey);
+                return Ok(false);
             }
+            JobStage::Rejected => {
+                println!("Request {} already rejected, skipping", pubkey);
+                return Ok(false);
+            }
+            JobStage::Failed if job.next_retry_at.is_some_and(|at| at > now) => {
+                return Ok(false);
+            }
+            _ => {}
         }
     }
 
-    Ok(processed)
+    println!("\nFound PENDING request {} (invoice #{})", pubkey, request.invoice_id);
+    println!("Authority: {}", request.authority);
+    println!("IPFS: {}", request.ipfs_hash);
+
+    extract_and_submit(oracle, tenant, &request, pubkey).await?;
+    Ok(true)
 }
 
 async fn extract_and_submit(
-    rpc_client: &RpcClient,
-    keypair: &Keypair,
-    program_id: &Pubkey,
+    oracle: &Oracle,
+    tenant: &Tenant,
     request: &InvoiceRequest,
     request_pubkey: &Pubkey,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let Oracle { rpc_client, keypair, store, retry: policy, config, .. } = oracle;
+
+    let mut job = store.get_or_discover(request_pubkey, request.timestamp, &request.org)?;
+    job.attempts += 1;
+    store.save(&job)?;
+    println!("Job {} attempt #{} (stage {:?})", job.request, job.attempts, job.stage);
+
+    let result: Result<(), Box<dyn std::error::Error>> = async {
+        // A previous run may have landed the transaction before timing out or crashing
+        if let Some(sig) = job.signature.as_deref() {
+            let sig = Signature::from_str(sig)?;
+            if let Some(Ok(())) = rpc_client.get_signature_status(&sig)? {
+                println!("Previous submission {} already landed", sig);
+                match job.rejection {
+                    Some(_) => store.reject(&mut job)?,
+                    None => store.advance(&mut job, JobStage::Confirmed)?,
+                }
+                return Ok(());
+            }
+        }
 
-    let api_key = env::var("OCR_API_KEY")
-        .expect("OCR_API_KEY must be set in .env file");
+        // Decided on an earlier attempt or by an operator; only the transaction is left
+        if let Some(rejection) = job.rejection {
+            return submit_rejection(oracle, &mut job, request, rejection);
+        }
 
+        let ocr_text = match job.ocr_text.clone() {
+            Some(text) => {
+                println!("Reusing cached OCR text");
+                text
+            }
+            None => {
+                // Don't pay for OCR on a document that already became an invoice
+                let (registry, _) = pda::document_registry(&request.org, &request.ipfs_hash);
+                if registry_claimed(oracle, &registry)? {
+                    let detail = format!("document {} was already processed", request.ipfs_hash);
+                    println!("Request cannot be processed: {}", detail);
+                    job.rejection = Some(Rejection::Duplicate);
+                    job.last_error = Some(detail);
+                    store.save(&job)?;
+                    return submit_rejection(oracle, &mut job, request, Rejection::Duplicate);
+                }
 
-    let ocr_url = format!(
-        "https://api.ocr.space/parse/imageurl?apikey={}&url=https://emerald-abundant-baboon-978.mypinata.cloud/ipfs/{}&language=eng&OCREngine=2",
-        api_key,
-        request.ipfs_hash
-    );
+                let ocr = tenant
+                    .ocr
+                    .extract_text(&request.ipfs_hash)
+                    .await
+                    .map_err(OcrError::from_provider)?;
+                println!("OCR Text extracted");
+                job.ocr_text = Some(ocr.text.clone());
+                job.ocr_line_confidence = ocr.line_confidence;
+                store.advance(&mut job, JobStage::OcrDone)?;
+                ocr.text
+            }
+        };
 
-    println!("Calling OCR API...");
-    let client = reqwest::Client::new();
-    let response = client.get(&ocr_url).send().await?;
-    let json: serde_json::Value = response.json().await?;
+        let org = fetch_org_config(oracle, &request.org)?;
+        let parsed = match job.parsed.clone() {
+            Some(parsed) => parsed,
+            None => {
+                let line_confidence = job.ocr_line_confidence.as_deref();
+                let parsed = parse_extraction(oracle, &request.org, &org, &ocr_text, line_confidence)?;
+                job.parsed = Some(parsed.clone());
+                store.advance(&mut job, JobStage::Parsed)?;
+                parsed
+            }
+        };
 
+        // Nothing an operator could approve fixes these, so tell the submitter right away
+        if let Some((rejection, detail)) = rejection_for(&parsed, &org, chrono::Utc::now().timestamp()) {
+            println!("Request cannot be processed: {}", detail);
+            job.rejection = Some(rejection);
+            job.last_error = Some(detail);
+            store.save(&job)?;
+            return submit_rejection(oracle, &mut job, request, rejection);
+        }
 
-    println!("\n===== RAW OCR API RESPONSE =====");
-    println!("{}", serde_json::to_string_pretty(&json)?);
-    println!("================================\n");
+        let reasons = review_reasons(&parsed, config);
+        if !reasons.is_empty() {
+            store.review(&mut job, &reasons.join("; "))?;
+            return Ok(());
+        }
 
+        let (vendor_pda, _) = pda::vendor(&request.org, &parsed.vendor);
+        let (fingerprint, _) =
+            pda::invoice_fingerprint_registry(&request.org, &vendor_pda, &parsed.invoice_number, parsed.amount);
+        if registry_claimed(oracle, &fingerprint)? {
+            let detail = format!(
+                "invoice {} from '{}' for {} was already processed",
+                parsed.invoice_number, parsed.vendor, parsed.amount
+            );
+            println!("Request cannot be processed: {}", detail);
+            job.rejection = Some(Rejection::Duplicate);
+            job.last_error = Some(detail);
+            store.save(&job)?;
+            return submit_rejection(oracle, &mut job, request, Rejection::Duplicate);
+        }
 
+        // The program sends these straight to audit; flag them so operators see why
+        job.amount_mismatch = !org.amount_within_tolerance(request.amount, parsed.amount);
+        if job.amount_mismatch {
+            println!(
+                "Amount mismatch: submitter claimed {}, document says {} (tolerance {} bps); invoice will await audit",
+                request.amount, parsed.amount, org.amount_tolerance_bps
+            );
+        }
 
-    let ocr_text = json["ParsedResults"][0]["ParsedText"]
-        .as_str()
-        .ok_or("Failed to extract OCR text")?;
-    println!("OCR Text extracted");
+        // Below the org's threshold the program also sends the invoice to audit
+        let confidence_bps = parsed.confidence_bps();
+        job.low_confidence = confidence_bps < org.min_confidence_bps;
+        if job.low_confidence {
+            println!(
+                "Low extraction confidence: {} bps (minimum {} bps); invoice will await audit",
+                confidence_bps, org.min_confidence_bps
+            );
+            let evidence = &parsed.evidence;
+            for (field, evidence) in [
+                ("vendor", &evidence.vendor),
+                ("amount", &evidence.amount),
+                ("due date", &evidence.due_date),
+                ("invoice number", &evidence.invoice_number),
+            ] {
+                if let Some(evidence) = evidence {
+                    println!("  {}: {}", field, evidence);
+                }
+            }
+        }
 
-    let (vendor, amount, mut due_date) = parse_invoice(ocr_text);
-    println!("Vendor: {}", vendor);
-    println!("Amount: ${}", amount as f64 / 1_000_000.0);
-    println!("Due Date: {}", due_date);
+        let tx = build_extraction_tx(rpc_client, keypair, request, &parsed)?;
 
-    // Ensure due date is in the future so on-chain checks pass
-    let now = chrono::Utc::now().timestamp();
-    if due_date <= now {
-        // fallback: 30 days from now
-        let fallback = now + 30 * 24 * 60 * 60;
-        println!(
-            "Due date not found or in past; using fallback {} (30 days ahead)",
-            fallback
-        );
-        due_date = fallback;
-    }
+        // Record the signature before sending so a crash mid-confirmation can be recovered
+        job.signature = Some(tx.signatures[0].to_string());
+        store.advance(&mut job, JobStage::Submitted)?;
 
-    // Derive PDAs used by process_extraction_result
-    let (invoice_pda, _) = Pubkey::find_program_address(
-        &[b"invoice", request.authority.as_ref()],
-        program_id,
-    );
+        println!("Submitting to Solana...");
+        let signature = rpc_client.send_and_confirm_transaction(&tx)?;
+        println!("Transaction: {}", signature);
+        store.advance(&mut job, JobStage::Confirmed)?;
 
-    // org_config PDA is derived from the ORG AUTHORITY (not the payer)
-    let org_authority_str = env::var("ORG_AUTHORITY_PUBKEY")
-        .expect("ORG_AUTHORITY_PUBKEY must be set in .env");
-    let org_authority = Pubkey::from_str(&org_authority_str)?;
-    let (org_config_pda, _) = Pubkey::find_program_address(
-        &[b"org_config", org_authority.as_ref()],
-        program_id,
-    );
+        // Optionally auto-request VRF after successful validation; mismatches skip the draw
+        if tenant.auto_request_vrf && !job.amount_mismatch && !job.low_confidence {
+            let (invoice_pda, _) = pda::invoice(&request.org, request.invoice_id);
+            if let Err(e) = request_vrf_for_invoice(rpc_client, keypair, &request.org, &invoice_pda).await {
+                eprintln!("VRF request failed: {}", e);
+            }
+        }
 
-    // Vendor PDA depends on org_config and parsed vendor name
-    let (vendor_pda, _) = Pubkey::find_program_address(
-        &[b"vendor", org_config_pda.as_ref(), vendor.as_bytes()],
-        program_id,
-    );
+        Ok(())
+    }
+    .await;
+
+    if let Err(e) = &result {
+        let class = retry::classify(e.as_ref());
+        let error = format!("{:?}: {}", class, e);
+        let rejection = match class {
+            _ if job.rejection.is_some() => None,
+            ErrorClass::Permanent => retry::rejection(e.as_ref()),
+            // Out of attempts at reading the document, so it is as good as unreadable
+            ErrorClass::Transient if policy.should_dead_letter(class, job.attempts) && e.is::<OcrError>() => {
+                Some(Rejection::OcrFailure)
+            }
+            ErrorClass::Transient => None,
+        };
+        if let Some(rejection) = rejection {
+            // The program refused the result, or the document could not be read, for a reason
+            // the submitter must fix; report it on-chain on the next retry tick instead of
+            // dead-lettering silently
+            job.rejection = Some(rejection);
+            store.fail(&mut job, &error, chrono::Utc::now().timestamp())?;
+        } else if policy.should_dead_letter(class, job.attempts) {
+            store.dead_letter(&mut job, &error)?;
+        } else {
+            let delay = policy.backoff(job.attempts);
+            store.fail(&mut job, &error, chrono::Utc::now().timestamp() + delay.as_secs() as i64)?;
+        }
+    }
+    result
+}
 
-    // Compute Anchor discriminator dynamically: sha256("global:process_extraction_result")[..8]
-    let mut hasher = Sha256::new();
-    hasher.update(b"global:process_extraction_result");
-    let disc: [u8; 8] = hasher.finalize()[..8].try_into().unwrap();
-    let mut data = disc.to_vec();
-
-    data.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
-    data.extend_from_slice(vendor.as_bytes());
-    data.extend_from_slice(&amount.to_le_bytes());
-    data.extend_from_slice(&due_date.to_le_bytes());
-
-    // Accounts must match ProcessResult in on-chain program (order matters):
-    // payer (signer), org_config (mut), vendor_account (readonly),
-    // invoice_request (mut), invoice_account (init, mut), system_program
-    let ix = Instruction {
-        program_id: *program_id,
-        accounts: vec![
-            // payer must be signer and writable
-            AccountMeta::new(keypair.pubkey(), true),
-            AccountMeta::new(org_config_pda, false),
-            AccountMeta::new_readonly(vendor_pda, false),
-            AccountMeta::new(*request_pubkey, false),
-            AccountMeta::new(invoice_pda, false),
-            AccountMeta::new_readonly(system_program::ID, false),
-        ],
-        data,
+fn parse_extraction(
+    oracle: &Oracle,
+    org_config: &Pubkey,
+    org: &OrgConfig,
+    ocr_text: &str,
+    line_confidence: Option<&[f64]>,
+) -> Result<ParsedInvoice, Box<dyn std::error::Error>> {
+    let result = parser::parse_invoice(ocr_text, line_confidence);
+    let vendor_candidates: Vec<String> = result.vendors.iter().map(|c| c.name.clone()).collect();
+
+    let mut evidence = FieldEvidence::default();
+    let (vendor, vendor_score, vendor_ties) = match match_vendor(oracle, org_config, &vendor_candidates)? {
+        Some(m) => {
+            // An exact match on a letterhead-only name is still weaker than a remit-to block
+            let candidate = result.vendors.iter().find(|c| c.name == m.candidate);
+            evidence.vendor = candidate.map(|c| {
+                Evidence {
+                    rule: format!("{} ~ registered vendor", c.reasons.join("+")),
+                    line: Some(c.line),
+                    ocr_confidence: None,
+                    confidence: m.score * (0.5 + 0.5 * c.weight()),
+                }
+                .with_ocr(line_confidence)
+            });
+            (m.vendor_name, m.score, m.tied_with)
+        }
+        None => (String::new(), 0.0, Vec::new()),
     };
 
-    println!("Submitting to Solana...");
-    let recent_blockhash = rpc_client.get_latest_blockhash()?;
-    let tx = Transaction::new_signed_with_payer(
-        &[ix],
-        Some(&keypair.pubkey()),
-        &[keypair],
-        recent_blockhash,
-    );
-
-    let signature = rpc_client.send_and_confirm_transaction(&tx)?;
-    println!("Transaction: {}", signature);
+    let decimals = mint_decimals(oracle, org)?;
+    let amount = match &result.amount {
+        Some(amount) => {
+            evidence.amount = Some(amount.evidence.clone());
+            amount
+                .value
+                .to_base_units(decimals)
+                .ok_or_else(|| format!("Amount {} does not fit a mint with {} decimals", amount.value, decimals))?
+        }
+        // Submitted as-is so the program rejects it with InvalidAmount
+        None => 0,
+    };
+    let (due_date, due_date_candidates) = match result.due_date {
+        DueDate::Found { date, evidence: found } => {
+            evidence.due_date = Some(found);
+            (parser::end_of_day(date), Vec::new())
+        }
+        DueDate::Ambiguous(dates) => (0, dates.into_iter().map(parser::end_of_day).collect()),
+        DueDate::NotFound => (0, Vec::new()),
+    };
+    let invoice_number = match result.invoice_number {
+        Some(number) => {
+            evidence.invoice_number = Some(number.evidence);
+            number.value
+        }
+        None => String::new(),
+    };
+    println!("Vendor: {} (score {:.2})", vendor, vendor_score);
+    println!("Amount: {} base units ({} decimals)", amount, decimals);
+    println!("Due Date: {}", due_date);
+    println!("Extraction confidence: {:.2}", evidence.confidence());
+
+    Ok(ParsedInvoice {
+        vendor,
+        vendor_candidates,
+        vendor_score,
+        vendor_ties,
+        amount,
+        due_date,
+        due_date_candidates,
+        invoice_number,
+        evidence,
+    })
+}
 
-    // Optionally auto-request VRF after successful validation
-    if env::var("AUTO_REQUEST_VRF").unwrap_or_default() == "1" {
-        if let Err(e) = request_vrf_for_invoice(rpc_client, keypair, program_id, &invoice_pda).await {
-            eprintln!("VRF request failed: {}", e);
+/// Why a parse cannot be submitted without an operator, if anything.
+fn review_reasons(parsed: &ParsedInvoice, config: &Config) -> Vec<String> {
+    let mut reasons = Vec::new();
+    if parsed.vendor_score < config.vendor_match.threshold {
+        reasons.push(format!(
+            "best vendor match '{}' scored {:.2} (< {:.2}) for candidates {:?}",
+            parsed.vendor, parsed.vendor_score, config.vendor_match.threshold, parsed.vendor_candidates
+        ));
+    }
+    if !parsed.vendor_ties.is_empty() {
+        reasons.push(format!(
+            "vendor '{}' cannot be told apart from {:?}",
+            parsed.vendor, parsed.vendor_ties
+        ));
+    }
+    if parsed.due_date == 0 {
+        if parsed.due_date_candidates.is_empty() {
+            reasons.push("no due date or payment terms found".to_string());
+        } else {
+            let dates: Vec<String> = parsed
+                .due_date_candidates
+                .iter()
+                .filter_map(|ts| chrono::DateTime::from_timestamp(*ts, 0))
+                .map(|dt| dt.date_naive().to_string())
+                .collect();
+            reasons.push(format!("due date is ambiguous: {}", dates.join(" or ")));
         }
     }
+    // The program keys its duplicate registry on it
+    if parsed.invoice_number.is_empty() {
+        reasons.push("no invoice number found".to_string());
+    }
+    reasons
+}
 
-    Ok(())
+/// Why the program would refuse `parsed` no matter who approves it, if it would.
+fn rejection_for(parsed: &ParsedInvoice, org: &OrgConfig, now: i64) -> Option<(Rejection, String)> {
+    if parsed.amount == 0 {
+        return Some((Rejection::OcrFailure, "no invoice total found in the OCR text".to_string()));
+    }
+    if parsed.amount > org.per_invoice_cap {
+        return Some((
+            Rejection::CapExceeded,
+            format!("amount {} exceeds the per-invoice cap {}", parsed.amount, org.per_invoice_cap),
+        ));
+    }
+    // 0 means unresolved, which goes to review instead
+    if parsed.due_date != 0 && parsed.due_date <= now {
+        return Some((Rejection::InvalidDueDate, format!("due date {} has already passed", parsed.due_date)));
+    }
+    None
 }
 
-fn parse_invoice(text: &str) -> (String, u64, i64) {
-    println!("\n===== PARSING INVOICE DATA =====");
-
-    // Extract vendor name - look for "Bill to" followed by name on next line
-    let vendor = if let Some(bill_to_pos) = text.find("Bill to") {
-        // Get text after "Bill to"
-        let after_bill_to = &text[bill_to_pos + 7..];
-
-        // Split by newlines and get the first non-empty line
-        let lines: Vec<&str> = after_bill_to.lines().collect();
-
-        // The name should be on the next line after "Bill to"
-        lines.iter()
-            .skip(1) // Skip the "Bill to" line itself
-            .find(|line| !line.trim().is_empty() && !line.contains("@")) // Skip empty lines and email
-            .map(|line| line.trim().to_string())
-            .unwrap_or_else(|| "Unknown Vendor".to_string())
-    } else {
-        // Fallback: Look for a capitalized name pattern
-        let name_re = Regex::new(r"([A-Z][a-z]+\s+[A-Z][a-z]+)").unwrap();
-        name_re.find(text)
-            .map(|m| m.as_str().to_string())
-            .unwrap_or_else(|| "Unknown Vendor".to_string())
-    };
+/// Whether a duplicate registry PDA exists and already points at an invoice.
+fn registry_claimed(oracle: &Oracle, registry: &Pubkey) -> Result<bool, Box<dyn std::error::Error>> {
+    let account = oracle
+        .rpc_client
+        .get_account_with_commitment(registry, oracle.rpc_client.commitment())?
+        .value;
+    Ok(match account {
+        Some(account) => decode::account::<InvoiceRegistry>(&account.data)?.invoice != Pubkey::default(),
+        None => false,
+    })
+}
 
-    println!("  Vendor Name: '{}'", vendor);
-
-    // Extract amount - look for $XX.XX followed by "due"
-    let amount_re = Regex::new(r"\$([0-9]+\.[0-9]{2})\s+due").unwrap();
-    let amount_str = amount_re
-        .captures(text)
-        .and_then(|c| c.get(1))
-        .map(|m| m.as_str())
-        .unwrap_or("0.00");
-    let amount_float: f64 = amount_str.parse().unwrap_or(0.0);
-    let amount = (amount_float * 1_000_000.0) as u64;
-
-    println!("  Amount: ${} (raw: {})", amount as f64 / 1_000_000.0, amount_str);
-
-    // Extract due date
-    let date_re = Regex::new(r"(January|February|March|April|May|June|July|August|September|October|November|December)\s+(\d{1,2}),\s+(\d{4})").unwrap();
-    let due_date = if let Some(caps) = date_re.captures(text) {
-        let month = match &caps[1] {
-            "January" => 1, "February" => 2, "March" => 3, "April" => 4,
-            "May" => 5, "June" => 6, "July" => 7, "August" => 8,
-            "September" => 9, "October" => 10, "November" => 11, "December" => 12,
-            _ => 1,
-        };
-        let day: u32 = caps[2].parse().unwrap_or(1);
-        let year: i32 = caps[3].parse().unwrap_or(2025);
-
-        chrono::NaiveDate::from_ymd_opt(year, month, day)
-            .and_then(|d| d.and_hms_opt(0, 0, 0))
-            .map(|dt| dt.and_utc().timestamp())
-            .unwrap_or(0)
-    } else {
-        0
-    };
+fn fetch_org_config(oracle: &Oracle, org_config: &Pubkey) -> Result<OrgConfig, Box<dyn std::error::Error>> {
+    Ok(decode::account(&oracle.rpc_client.get_account_data(org_config)?)?)
+}
+
+/// Decimals of the org's payment mint, which on-chain amounts are denominated in.
+fn mint_decimals(oracle: &Oracle, org: &OrgConfig) -> Result<u8, Box<dyn std::error::Error>> {
+    let mint: Mint = decode::account(&oracle.rpc_client.get_account_data(&org.mint)?)?;
+    Ok(mint.decimals)
+}
 
-    if due_date > 0 {
-        let date_str = chrono::NaiveDateTime::from_timestamp_opt(due_date, 0)
-            .map(|dt| dt.format("%B %d, %Y").to_string())
-            .unwrap_or_else(|| "Invalid".to_string());
-        println!("  Due Date: {} (Unix: {})", date_str, due_date);
-    } else {
-        println!("  Due Date: Not found");
+/// Fuzzy-matches the seller candidates against the org's registered vendors. None when
+/// the org has no vendors or nothing was found in the document.
+fn match_vendor(
+    oracle: &Oracle,
+    org_config: &Pubkey,
+    candidates: &[String],
+) -> Result<Option<VendorMatch>, Box<dyn std::error::Error>> {
+    let vendors = discovery::fetch_org_vendors(&oracle.rpc_client, &invoice_claim_client::ID, org_config)?;
+    println!("Matching against {} registered vendor(s)", vendors.len());
+
+    let best = matching::best_match(candidates, &vendors);
+    if let Some(m) = &best {
+        println!("  '{}' ~ '{}' ({:.2})", m.candidate, m.vendor_name, m.score);
+        if !m.tied_with.is_empty() {
+            println!("  equally close: {:?}", m.tied_with);
+        }
     }
+    Ok(best)
+}
 
-    println!("================================\n");
+fn build_extraction_tx(
+    rpc_client: &RpcClient,
+    keypair: &Keypair,
+    request: &InvoiceRequest,
+    parsed: &ParsedInvoice,
+) -> Result<Transaction, Box<dyn std::error::Error>> {
+    // The result goes to the org the submitter named on the request
+    let ix = instructions::process_extraction_result(
+        &keypair.pubkey(),
+        &request.org,
+        request.invoice_id,
+        &request.ipfs_hash,
+        parsed.vendor.clone(),
+        parsed.amount,
+        parsed.due_date,
+        parsed.invoice_number.clone(),
+        parsed.confidence_bps(),
+    );
 
-    (vendor, amount, due_date)
+    let recent_blockhash = rpc_client.get_latest_blockhash()?;
+    Ok(Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&keypair.pubkey()),
+        &[keypair],
+        recent_blockhash,
+    ))
+}
+
+/// Marks the request Failed on-chain and records the job as Rejected once it lands.
+fn submit_rejection(
+    oracle: &Oracle,
+    job: &mut Job,
+    request: &InvoiceRequest,
+    rejection: Rejection,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Oracle { rpc_client, keypair, store, .. } = oracle;
+    let ix = instructions::reject_extraction(&keypair.pubkey(), &request.org, request.invoice_id, rejection.into());
+    let recent_blockhash = rpc_client.get_latest_blockhash()?;
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&keypair.pubkey()), &[keypair], recent_blockhash);
+
+    // Recorded before sending, like extraction results, so a landed rejection is recognised
+    job.signature = Some(tx.signatures[0].to_string());
+    store.save(job)?;
+
+    println!("Rejecting request as {:?}...", rejection);
+    let signature = rpc_client.send_and_confirm_transaction(&tx)?;
+    println!("Transaction: {}", signature);
+    store.reject(job)
 }
 
 // Send our program's request_invoice_audit_vrf instruction
 async fn request_vrf_for_invoice(
     rpc_client: &RpcClient,
     keypair: &Keypair,
-    program_id: &Pubkey,
+    org_config: &Pubkey,
     invoice_pda: &Pubkey,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Derive org_config PDA from ORG_AUTHORITY_PUBKEY
-    let org_authority_str = env::var("ORG_AUTHORITY_PUBKEY")
-        .expect("ORG_AUTHORITY_PUBKEY must be set in .env");
-    let org_authority = Pubkey::from_str(&org_authority_str)?;
-    let (org_config_pda, _) = Pubkey::find_program_address(
-        &[b"org_config", org_authority.as_ref()],
-        program_id,
-    );
-
-    // Oracle queue pubkey must match on-chain DEFAULT_QUEUE constant
-    let queue_str = env::var("QUEUE_PUBKEY")
-        .expect("QUEUE_PUBKEY must be set in .env to auto-request VRF");
-    let queue_pk = Pubkey::from_str(&queue_str)?;
-
-    // Discriminator for global:request_invoice_audit_vrf
-    let mut h = Sha256::new();
-    h.update(b"global:request_invoice_audit_vrf");
-    let disc: [u8; 8] = h.finalize()[..8].try_into().unwrap();
-
     // Single u8 client_seed argument; use a simple deterministic seed
     let client_seed: u8 = 42;
 
-    let mut data = Vec::with_capacity(9);
-    data.extend_from_slice(&disc);
-    data.push(client_seed);
-
-    let ix = Instruction {
-        program_id: *program_id,
-        accounts: vec![
-            AccountMeta::new(keypair.pubkey(), true),       // payer (signer, mut)
-            AccountMeta::new(org_config_pda, false),        // org_config (mut)
-            AccountMeta::new(*invoice_pda, false),          // invoice_account (mut)
-            AccountMeta::new(queue_pk, false),              // oracle_queue (mut)
-        ],
-        data,
-    };
+    let ix = instructions::request_invoice_audit_vrf(&keypair.pubkey(), org_config, invoice_pda, client_seed);
 
     let recent_blockhash = rpc_client.get_latest_blockhash()?;
     let tx = Transaction::new_signed_with_payer(
@@ -0,0 +1,171 @@
+use invoice_claim_client::VendorAccount;
+
+/// Legal-form and filler words that say nothing about which vendor it is
+const IGNORED_WORDS: &[&str] = &[
+    "the", "inc", "incorporated", "llc", "ltd", "limited", "corp", "corporation", "co", "company",
+    "gmbh", "plc", "sa", "bv", "pty",
+];
+
+/// Best registered vendor for an invoice's seller candidates
+#[derive(Clone, Debug)]
+pub struct VendorMatch {
+    /// Canonical on-chain `vendor_name`, used for the vendor PDA
+    pub vendor_name: String,
+    /// Seller text from the document that matched it
+    pub candidate: String,
+    /// Similarity of the normalised names, 0.0..=1.0
+    pub score: f64,
+    /// Other vendors the document matches exactly as well, legal form included;
+    /// non-empty means the match is ambiguous
+    pub tied_with: Vec<String>,
+}
+
+/// Lowercases, drops punctuation and legal suffixes, and collapses whitespace,
+/// so "ACME Corp." and "Acme  Corp" compare equal.
+pub fn normalize(name: &str) -> String {
+    normalize_keeping_legal_form(name)
+        .split(' ')
+        .filter(|word| !IGNORED_WORDS.contains(word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Like `normalize` but keeps legal forms, so "Acme Inc" and "Acme LLC" still differ.
+fn normalize_keeping_legal_form(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { ' ' })
+        .collect();
+    cleaned.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (normalize(a), normalize(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    strsim::normalized_levenshtein(&a, &b)
+}
+
+/// Scores every candidate against every vendor; for one vendor, ties go to the
+/// higher-ranked candidate. Vendors that tie once legal forms are ignored are told
+/// apart by their full names, and reported in `tied_with` if even those tie.
+pub fn best_match(candidates: &[String], vendors: &[VendorAccount]) -> Option<VendorMatch> {
+    let mut best: Option<(VendorMatch, f64)> = None;
+
+    for vendor in vendors {
+        let Some((m, full_name_score)) = score_vendor(candidates, vendor) else {
+            continue;
+        };
+        match &mut best {
+            Some((b, b_full)) if (m.score, full_name_score) < (b.score, *b_full) => {}
+            Some((b, b_full)) if (m.score, full_name_score) == (b.score, *b_full) => {
+                b.tied_with.push(m.vendor_name);
+            }
+            _ => best = Some((m, full_name_score)),
+        }
+    }
+
+    best.map(|(m, _)| m)
+}
+
+/// `vendor`'s best score and the first candidate reaching it, with the best full-name
+/// similarity among the candidates that do.
+fn score_vendor(candidates: &[String], vendor: &VendorAccount) -> Option<(VendorMatch, f64)> {
+    let mut best: Option<(VendorMatch, f64)> = None;
+
+    for candidate in candidates {
+        let score = similarity(candidate, &vendor.vendor_name);
+        let full_name_score = strsim::normalized_levenshtein(
+            &normalize_keeping_legal_form(candidate),
+            &normalize_keeping_legal_form(&vendor.vendor_name),
+        );
+        match &mut best {
+            Some((b, b_full)) if score == b.score => *b_full = b_full.max(full_name_score),
+            Some((b, _)) if score < b.score => {}
+            _ => {
+                best = Some((
+                    VendorMatch {
+                        vendor_name: vendor.vendor_name.clone(),
+                        candidate: candidate.clone(),
+                        score,
+                        tied_with: Vec::new(),
+                    },
+                    full_name_score,
+                ))
+            }
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vendor(name: &str) -> VendorAccount {
+        VendorAccount {
+            org: Default::default(),
+            vendor_name: name.to_string(),
+            wallet: Default::default(),
+            total_paid: 0,
+            last_payment: 0,
+            payment_count: 0,
+            period_paid: 0,
+            current_period: 0,
+            is_active: true,
+            currency_preference: Default::default(),
+        }
+    }
+
+    fn candidates(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn legal_suffixes_and_punctuation_are_ignored() {
+        let vendors = [vendor("Initech"), vendor("ACME Corporation")];
+        let m = best_match(&candidates(&["Acme  Corp."]), &vendors).unwrap();
+        assert_eq!(m.vendor_name, "ACME Corporation");
+        assert_eq!(m.score, 1.0);
+    }
+
+    #[test]
+    fn picks_the_candidate_that_matches_a_registered_vendor() {
+        // The remit-to name ranks first but only the letterhead name is registered
+        let vendors = [vendor("Northwind Traders Ltd"), vendor("Contoso Inc")];
+        let m = best_match(&candidates(&["Northwind Payments", "Northwind Traders"]), &vendors).unwrap();
+        assert_eq!(m.vendor_name, "Northwind Traders Ltd");
+        assert_eq!(m.candidate, "Northwind Traders");
+    }
+
+    #[test]
+    fn ties_go_to_the_higher_ranked_candidate() {
+        let vendors = [vendor("Globex")];
+        let m = best_match(&candidates(&["Globex Corp", "GLOBEX"]), &vendors).unwrap();
+        assert_eq!(m.candidate, "Globex Corp");
+    }
+
+    #[test]
+    fn vendors_differing_only_by_legal_form_are_told_apart_or_flagged() {
+        let vendors = [vendor("Acme LLC"), vendor("Acme Inc")];
+
+        let m = best_match(&candidates(&["ACME, Inc."]), &vendors).unwrap();
+        assert_eq!(m.vendor_name, "Acme Inc");
+        assert!(m.tied_with.is_empty());
+
+        // Nothing on the document says which Acme it is
+        let m = best_match(&candidates(&["ACME"]), &vendors).unwrap();
+        assert_eq!(m.vendor_name, "Acme LLC");
+        assert_eq!(m.tied_with, ["Acme Inc"]);
+    }
+
+    #[test]
+    fn ocr_typos_score_below_exact_matches() {
+        let vendors = [vendor("Blue Ridge Plumbing")];
+        let m = best_match(&candidates(&["Blue Rldge Plumbinq"]), &vendors).unwrap();
+        assert!(m.score > 0.8 && m.score < 1.0, "score {}", m.score);
+        assert!(best_match(&candidates(&["Blue Ridge Plumbing"]), &[]).is_none());
+    }
+}
@@ -0,0 +1,478 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::config::{OcrConfig, OcrProviderKind};
+
+const OCR_SPACE_URL: &str = "https://api.ocr.space/parse/image";
+/// Invoices are single scanned pages; anything bigger is not one
+const MAX_DOCUMENT_BYTES: u64 = 20 * 1024 * 1024;
+const TESSERACT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Recognized text, with the engine's confidence per line when it reports one
+#[derive(Clone, Debug)]
+pub struct OcrText {
+    pub text: String,
+    /// Mean word confidence (0.0..=1.0) of each line of `text`, blank lines included
+    pub line_confidence: Option<Vec<f64>>,
+}
+
+/// Why a document could not be turned into text
+#[derive(Debug)]
+pub enum OcrError {
+    /// The document itself is the problem (bad CID, OCR engine gave up on it); retrying won't help
+    Unreadable(String),
+    /// Fetching or recognizing failed; the cause decides whether a retry can help
+    Failed(Box<dyn std::error::Error>),
+}
+
+impl OcrError {
+    /// Wraps a provider error, keeping it as is if the provider already classified it.
+    pub fn from_provider(error: Box<dyn std::error::Error>) -> Self {
+        match error.downcast::<OcrError>() {
+            Ok(error) => *error,
+            Err(error) => OcrError::Failed(error),
+        }
+    }
+}
+
+impl std::fmt::Display for OcrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OcrError::Unreadable(reason) => write!(f, "Unreadable document: {}", reason),
+            OcrError::Failed(cause) => write!(f, "OCR failed: {}", cause),
+        }
+    }
+}
+
+impl std::error::Error for OcrError {}
+
+/// Turns the document behind an IPFS hash into plain text.
+#[async_trait]
+pub trait OcrProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn extract_text(&self, ipfs_hash: &str) -> Result<OcrText, Box<dyn std::error::Error>>;
+}
+
+/// Builds the provider selected by `ocr.provider`.
+pub fn provider_from_config(config: &OcrConfig) -> Result<Box<dyn OcrProvider>, Box<dyn std::error::Error>> {
+    match config.provider {
+        OcrProviderKind::Ocrspace => {
+            let api_key = config
+                .api_key
+                .clone()
+                .ok_or("OCR_API_KEY must be set for the ocrspace provider")?;
+            Ok(Box::new(OcrSpaceProvider {
+                api_key,
+                gateway: config.ipfs_gateway.clone(),
+                language: config.language.clone(),
+                record_dir: config.record_dir.clone(),
+                client: reqwest::Client::new(),
+            }))
+        }
+        OcrProviderKind::Tesseract => Ok(Box::new(TesseractProvider {
+            binary: config.tesseract_bin.clone(),
+            gateway: config.ipfs_gateway.clone(),
+            language: config.language.clone(),
+            client: reqwest::Client::new(),
+            max_document_bytes: MAX_DOCUMENT_BYTES,
+            timeout: TESSERACT_TIMEOUT,
+        })),
+        OcrProviderKind::Fixture => {
+            let dir = config
+                .fixture_dir
+                .clone()
+                .ok_or("OCR_FIXTURE_DIR must be set for the fixture provider")?;
+            Ok(Box::new(FixtureProvider { dir }))
+        }
+    }
+}
+
+/// The hash ends up in gateway URLs and file paths, so anything but a bare CID
+/// (e.g. `../`, `?`, `/`) is refused before it is used.
+fn check_cid(ipfs_hash: &str) -> Result<(), OcrError> {
+    if ipfs_hash.is_empty() || !ipfs_hash.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        return Err(OcrError::Unreadable(format!("invalid IPFS hash {:?}", ipfs_hash)));
+    }
+    Ok(())
+}
+
+fn document_url(gateway: &str, ipfs_hash: &str) -> String {
+    format!("{}/{}", gateway.trim_end_matches('/'), ipfs_hash)
+}
+
+// OCR.space answers with { ParsedResults: [{ ParsedText }] }; fixtures use the same shape
+fn parsed_text(json: &serde_json::Value) -> Result<String, Box<dyn std::error::Error>> {
+    if json["IsErroredOnProcessing"].as_bool() == Some(true) {
+        return Err(OcrError::Unreadable(format!("OCR processing failed: {}", json["ErrorMessage"])).into());
+    }
+
+    json["ParsedResults"][0]["ParsedText"]
+        .as_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| "Failed to extract OCR text".into())
+}
+
+/// Hosted OCR.space API. The key travels in the `apikey` header, not the URL.
+pub struct OcrSpaceProvider {
+    pub api_key: String,
+    pub gateway: String,
+    pub language: String,
+    /// When set, raw responses are written here so they can be replayed by `FixtureProvider`
+    pub record_dir: Option<PathBuf>,
+    pub client: reqwest::Client,
+}
+
+#[async_trait]
+impl OcrProvider for OcrSpaceProvider {
+    fn name(&self) -> &'static str {
+        "ocrspace"
+    }
+
+    async fn extract_text(&self, ipfs_hash: &str) -> Result<OcrText, Box<dyn std::error::Error>> {
+        check_cid(ipfs_hash)?;
+        let url = document_url(&self.gateway, ipfs_hash);
+
+        println!("Calling OCR.space API...");
+        let response = self
+            .client
+            .post(OCR_SPACE_URL)
+            .header("apikey", &self.api_key)
+            .form(&[
+                ("url", url.as_str()),
+                ("language", self.language.as_str()),
+                ("OCREngine", "2"),
+            ])
+            .send()
+            .await?
+            .error_for_status()?;
+        let json: serde_json::Value = response.json().await?;
+
+        println!("\n===== RAW OCR API RESPONSE =====");
+        println!("{}", serde_json::to_string_pretty(&json)?);
+        println!("================================\n");
+
+        if let Some(dir) = &self.record_dir {
+            std::fs::create_dir_all(dir)?;
+            let path = dir.join(format!("{}.json", ipfs_hash));
+            std::fs::write(&path, serde_json::to_vec_pretty(&json)?)?;
+            println!("Recorded OCR response to {}", path.display());
+        }
+
+        // OCR.space does not report word confidence
+        Ok(OcrText { text: parsed_text(&json)?, line_confidence: None })
+    }
+}
+
+/// Local `tesseract` binary; the document is fetched from the gateway first.
+pub struct TesseractProvider {
+    pub binary: String,
+    pub gateway: String,
+    pub language: String,
+    pub client: reqwest::Client,
+    pub max_document_bytes: u64,
+    /// How long tesseract may run before it is killed
+    pub timeout: Duration,
+}
+
+impl TesseractProvider {
+    /// Downloads the document, refusing it once it grows past `max_document_bytes`.
+    async fn download(&self, url: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let too_large = || OcrError::Unreadable(format!("document exceeds {} bytes", self.max_document_bytes));
+
+        let mut response = self.client.get(url).send().await?.error_for_status()?;
+        if response.content_length().is_some_and(|len| len > self.max_document_bytes) {
+            return Err(too_large().into());
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if (bytes.len() + chunk.len()) as u64 > self.max_document_bytes {
+                return Err(too_large().into());
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok(bytes)
+    }
+}
+
+/// tesseract only reads images; a PDF would fail inside it with a less useful error.
+fn check_image(bytes: &[u8]) -> Result<(), OcrError> {
+    if bytes.starts_with(b"%PDF-") {
+        return Err(OcrError::Unreadable("PDF documents are not supported by tesseract".to_string()));
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl OcrProvider for TesseractProvider {
+    fn name(&self) -> &'static str {
+        "tesseract"
+    }
+
+    async fn extract_text(&self, ipfs_hash: &str) -> Result<OcrText, Box<dyn std::error::Error>> {
+        check_cid(ipfs_hash)?;
+        let url = document_url(&self.gateway, ipfs_hash);
+
+        println!("Downloading document for tesseract...");
+        let bytes = self.download(&url).await?;
+        check_image(&bytes)?;
+
+        // Removed when dropped, whichever way this returns
+        let mut file = tempfile::Builder::new().prefix("invoice-oracle-").tempfile()?;
+        file.write_all(&bytes)?;
+        file.flush()?;
+
+        println!("Running {}...", self.binary);
+        let command = tokio::process::Command::new(&self.binary)
+            .arg(file.path())
+            .arg("stdout")
+            .arg("-l")
+            .arg(&self.language)
+            .arg("tsv")
+            .kill_on_drop(true)
+            .output();
+        let output = tokio::time::timeout(self.timeout, command)
+            .await
+            .map_err(|_| format!("{} timed out after {}s", self.binary, self.timeout.as_secs()))??;
+
+        if !output.status.success() {
+            return Err(format!(
+                "tesseract exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into());
+        }
+
+        Ok(tsv_text(&String::from_utf8(output.stdout)?))
+    }
+}
+
+/// Rebuilds plain text from tesseract's TSV output, one line per recognized text line
+/// and a blank line between paragraphs, keeping each line's mean word confidence.
+fn tsv_text(tsv: &str) -> OcrText {
+    let mut lines: Vec<(String, Vec<f64>)> = Vec::new();
+    let mut current: Option<(&str, &str, &str, &str)> = None;
+
+    // level page block par line word left top width height conf text
+    for row in tsv.lines().skip(1) {
+        let cols: Vec<&str> = row.splitn(12, '\t').collect();
+        if cols.len() < 12 || cols[0] != "5" {
+            continue;
+        }
+        let word = cols[11].trim();
+        if word.is_empty() {
+            continue;
+        }
+
+        let key = (cols[1], cols[2], cols[3], cols[4]);
+        if current != Some(key) {
+            if let Some((page, block, par, _)) = current
+                && (page, block, par) != (key.0, key.1, key.2)
+            {
+                lines.push((String::new(), Vec::new()));
+            }
+            lines.push((String::new(), Vec::new()));
+            current = Some(key);
+        }
+
+        let (text, confidences) = lines.last_mut().unwrap();
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        text.push_str(word);
+        if let Ok(conf) = cols[10].parse::<f64>()
+            && conf >= 0.0
+        {
+            confidences.push((conf / 100.0).min(1.0));
+        }
+    }
+
+    let text = lines.iter().map(|(text, _)| text.as_str()).collect::<Vec<_>>().join("\n");
+    let line_confidence = lines
+        .iter()
+        .map(|(_, c)| if c.is_empty() { 1.0 } else { c.iter().sum::<f64>() / c.len() as f64 })
+        .collect();
+    OcrText { text, line_confidence: Some(line_confidence) }
+}
+
+/// Replays recorded OCR.space responses from `<dir>/<ipfs_hash>.json`, for offline runs and CI.
+pub struct FixtureProvider {
+    pub dir: PathBuf,
+}
+
+#[async_trait]
+impl OcrProvider for FixtureProvider {
+    fn name(&self) -> &'static str {
+        "fixture"
+    }
+
+    async fn extract_text(&self, ipfs_hash: &str) -> Result<OcrText, Box<dyn std::error::Error>> {
+        check_cid(ipfs_hash)?;
+        let path = self.dir.join(format!("{}.json", ipfs_hash));
+        println!("Replaying OCR fixture {}", path.display());
+
+        let raw = tokio::fs::read(&path)
+            .await
+            .map_err(|e| format!("No OCR fixture at {}: {}", path.display(), e))?;
+        let json: serde_json::Value = serde_json::from_slice(&raw)?;
+
+        Ok(OcrText { text: parsed_text(&json)?, line_confidence: None })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext";
+
+    /// A level 5 (word) row of tesseract's TSV output.
+    fn word(block: u32, par: u32, line: u32, n: u32, conf: &str, text: &str) -> String {
+        format!("5\t1\t{}\t{}\t{}\t{}\t{}\t{}\t120\t40\t{}\t{}", block, par, line, n, n * 150, line * 50, conf, text)
+    }
+
+    fn invoice_tsv() -> String {
+        [
+            HEADER.to_string(),
+            "1\t1\t0\t0\t0\t0\t0\t0\t2480\t3508\t-1\t".to_string(),
+            "2\t1\t1\t0\t0\t0\t150\t120\t600\t90\t-1\t".to_string(),
+            "3\t1\t1\t1\t0\t0\t150\t120\t600\t90\t-1\t".to_string(),
+            "4\t1\t1\t1\t1\t0\t150\t120\t600\t40\t-1\t".to_string(),
+            word(1, 1, 1, 1, "96.5", "ACME"),
+            word(1, 1, 1, 2, "91", "Corp"),
+            "4\t1\t1\t1\t2\t0\t150\t170\t600\t40\t-1\t".to_string(),
+            word(1, 1, 2, 1, "88", "12"),
+            word(1, 1, 2, 2, "80", "Main"),
+            // Words tesseract could not score don't count towards the line
+            word(1, 1, 2, 3, "-1", "St"),
+            "2\t1\t2\t0\t0\t0\t150\t400\t600\t40\t-1\t".to_string(),
+            word(2, 1, 1, 1, "60", "Total:"),
+            word(2, 1, 1, 2, "-1", " "),
+            word(2, 1, 1, 3, "70", "$40.00"),
+        ]
+        .join("\n")
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn tsv_text_rebuilds_lines_and_paragraphs() {
+        let ocr = tsv_text(&invoice_tsv());
+        assert_eq!(ocr.text, "ACME Corp\n12 Main St\n\nTotal: $40.00");
+
+        let confidence = ocr.line_confidence.unwrap();
+        assert_eq!(confidence.len(), 4);
+        for (actual, expected) in confidence.into_iter().zip([0.9375, 0.84, 1.0, 0.65]) {
+            assert_close(actual, expected);
+        }
+    }
+
+    #[test]
+    fn tsv_line_confidence_scales_field_evidence() {
+        let ocr = tsv_text(&invoice_tsv());
+        let result = crate::parser::parse_invoice(&ocr.text, ocr.line_confidence.as_deref());
+
+        let amount = result.amount.unwrap();
+        assert_eq!(amount.evidence.line, Some(4));
+        assert_close(amount.evidence.ocr_confidence.unwrap(), 0.65);
+        // "total" is worth 0.80 on its own
+        assert_close(amount.evidence.confidence, 0.8 * 0.65);
+    }
+
+    #[test]
+    fn check_cid_accepts_v0_and_v1() {
+        assert!(check_cid("QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG").is_ok());
+        assert!(check_cid("bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi").is_ok());
+    }
+
+    /// Serves `body` to the first request on a local port, announcing its length only if asked.
+    async fn serve_once(body: &'static [u8], content_length: bool) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).await;
+            let header = if content_length {
+                format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len())
+            } else {
+                "HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n".to_string()
+            };
+            let _ = socket.write_all(header.as_bytes()).await;
+            let _ = socket.write_all(body).await;
+        });
+        format!("http://{}", addr)
+    }
+
+    fn tesseract(binary: &str, gateway: String) -> TesseractProvider {
+        TesseractProvider {
+            binary: binary.to_string(),
+            gateway,
+            language: "eng".to_string(),
+            client: reqwest::Client::new(),
+            max_document_bytes: 16,
+            timeout: Duration::from_millis(200),
+        }
+    }
+
+    const CID: &str = "QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG";
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    fn assert_unreadable(error: Box<dyn std::error::Error>, reason: &str) {
+        match OcrError::from_provider(error) {
+            OcrError::Unreadable(r) => assert!(r.contains(reason), "{}", r),
+            other => panic!("expected Unreadable, got {}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn tesseract_refuses_oversized_documents() {
+        for content_length in [true, false] {
+            let gateway = serve_once(&[0u8; 17], content_length).await;
+            let error = tesseract("tesseract", gateway).extract_text(CID).await.unwrap_err();
+            assert_unreadable(error, "exceeds 16 bytes");
+        }
+    }
+
+    #[tokio::test]
+    async fn tesseract_refuses_pdfs() {
+        let gateway = serve_once(b"%PDF-1.7\n%", true).await;
+        let error = tesseract("tesseract", gateway).extract_text(CID).await.unwrap_err();
+        assert_unreadable(error, "PDF");
+        assert!(check_image(PNG).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn tesseract_is_killed_after_the_timeout() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let binary = dir.path().join("tesseract");
+        std::fs::write(&binary, "#!/bin/sh\nsleep 10\n").unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let gateway = serve_once(PNG, true).await;
+        let started = std::time::Instant::now();
+        let error = tesseract(binary.to_str().unwrap(), gateway).extract_text(CID).await.unwrap_err();
+
+        assert!(error.to_string().contains("timed out"), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn check_cid_rejects_paths_and_urls() {
+        for hash in ["", "../../etc/passwd", "Qm123/evil", "Qm123?x=1", "Qm 123", "Qm123\\x"] {
+            assert!(check_cid(hash).is_err(), "{:?} should be rejected", hash);
+        }
+    }
+}
@@ -0,0 +1,945 @@
+use chrono::NaiveDate;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Matches the on-chain `VendorAccount.vendor_name` limit; the PDA seed is a hash of it
+pub const MAX_VENDOR_NAME_LEN: usize = 50;
+
+/// Labels introducing the buyer's block; nothing inside it is the seller
+const BUYER_LABELS: &[&str] = &[
+    "bill to", "billed to", "invoice to", "sold to", "ship to", "shipped to", "deliver to", "customer",
+];
+
+/// Labels introducing the seller, strongest signal first
+const SELLER_LABELS: &[(&str, u32, &str)] = &[
+    ("remit payment to", 100, "remit-to"),
+    ("remit to", 100, "remit-to"),
+    ("make checks payable to", 100, "payable-to"),
+    ("payable to", 95, "payable-to"),
+    ("pay to", 95, "payable-to"),
+    ("from", 90, "from"),
+    ("seller", 90, "seller"),
+    ("supplier", 90, "seller"),
+    ("vendor", 90, "seller"),
+    ("issued by", 90, "seller"),
+];
+
+/// Labels preceding the payable amount, most specific first; "subtotal" never matches
+const AMOUNT_LABELS: &[(&str, u32)] = &[
+    ("amount due", 100),
+    ("balance due", 100),
+    ("total due", 100),
+    ("amount payable", 100),
+    ("grand total", 90),
+    ("total", 80),
+    ("balance", 70),
+    ("amount", 60),
+];
+
+const CURRENCY_RE: &str = r"[$€£¥]|\b(?:USD|EUR|GBP|JPY|CHF|CAD|AUD|USDC|USDT)\b";
+// A digit run with any mix of '.', ',', apostrophe and (narrow) no-break space separators
+const NUMBER_RE: &str = r"\d(?:[\d.,'\u{a0}\u{202f}]*\d)?";
+
+const MONTHS_RE: &str = r"jan(?:uary)?|feb(?:ruary)?|mar(?:ch)?|apr(?:il)?|may|june?|july?|aug(?:ust)?|sep(?:t(?:ember)?)?|oct(?:ober)?|nov(?:ember)?|dec(?:ember)?";
+
+// "Invoice No.", "Inv #", "Bill Number", or "Invoice:" directly followed by the number
+const INVOICE_NUMBER_LABEL_RE: &str = r"(?i)\b(?:invoice|inv|bill)\s*(?:no\b\.?|number\b|num\b|nr\b\.?|#|id\b)\s*[:.#-]?\s*|\binvoice\s*[:#]\s*";
+
+/// Longest invoice number the program accepts
+pub const MAX_INVOICE_NUMBER_LEN: usize = 32;
+
+const TAX_ID_RE: &str = r"(?i)\b(tax\s*id|vat(\s*(no|number|reg))?|ein|abn|gst(in)?|tin|company\s*(no|number|reg))\b";
+const COMPANY_SUFFIX_RE: &str = r"(?i)\b(inc|llc|ltd|limited|gmbh|corp|corporation|co|company|plc|s\.?a|b\.?v|pty)\.?$";
+
+/// A money amount as written, kept as an integer and a scale so no float rounding is involved
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Amount {
+    /// All digits of the number, e.g. 125000 for "1,250.00"
+    pub digits: u128,
+    /// Number of those digits after the decimal separator
+    pub scale: u32,
+    /// Currency symbol or ISO code next to the number, if any
+    pub currency: Option<String>,
+}
+
+impl Amount {
+    /// Converts to base units of a mint with `decimals`. None if the amount has more
+    /// fractional precision than the mint or does not fit in a u64.
+    pub fn to_base_units(&self, decimals: u8) -> Option<u64> {
+        let decimals = u32::from(decimals);
+        let units = if self.scale <= decimals {
+            self.digits.checked_mul(10u128.checked_pow(decimals - self.scale)?)?
+        } else {
+            let divisor = 10u128.checked_pow(self.scale - decimals)?;
+            if !self.digits.is_multiple_of(divisor) {
+                return None;
+            }
+            self.digits / divisor
+        };
+        u64::try_from(units).ok()
+    }
+}
+
+impl std::fmt::Display for Amount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let divisor = 10u128.pow(self.scale);
+        write!(f, "{}", self.digits / divisor)?;
+        if self.scale > 0 {
+            write!(f, ".{:0width$}", self.digits % divisor, width = self.scale as usize)?;
+        }
+        if let Some(currency) = &self.currency {
+            write!(f, " {}", currency)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where an extracted value came from and how far it can be trusted
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Evidence {
+    /// Rule that produced the value, e.g. "label 'amount due'" or "net-terms"
+    pub rule: String,
+    /// 1-based line of the OCR text the value was read from
+    pub line: Option<usize>,
+    /// Mean OCR word confidence of that line, when the OCR provider reports one
+    pub ocr_confidence: Option<f64>,
+    /// 0.0..=1.0: how reliable the rule is, scaled by `ocr_confidence`
+    pub confidence: f64,
+}
+
+impl Evidence {
+    fn rule(rule: impl Into<String>, line_index: usize, confidence: f64) -> Self {
+        Evidence { rule: rule.into(), line: Some(line_index + 1), ocr_confidence: None, confidence }
+    }
+
+    /// A value typed in by an operator, which is taken as certain.
+    pub fn operator() -> Self {
+        Evidence { rule: "operator".to_string(), line: None, ocr_confidence: None, confidence: 1.0 }
+    }
+
+    /// Scales the confidence by the OCR engine's confidence in the source line.
+    pub fn with_ocr(mut self, line_confidence: Option<&[f64]>) -> Self {
+        if let Some(ocr) = self.line.and_then(|line| line_confidence?.get(line - 1).copied()) {
+            self.ocr_confidence = Some(ocr);
+            self.confidence *= ocr;
+        }
+        self
+    }
+}
+
+impl std::fmt::Display for Evidence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.rule)?;
+        if let Some(line) = self.line {
+            write!(f, ", line {}", line)?;
+        }
+        if let Some(ocr) = self.ocr_confidence {
+            write!(f, ", OCR {:.2}", ocr)?;
+        }
+        write!(f, ", confidence {:.2}", self.confidence)
+    }
+}
+
+/// An extracted value with its evidence
+#[derive(Clone, Debug, PartialEq)]
+pub struct Extracted<T> {
+    pub value: T,
+    pub evidence: Evidence,
+}
+
+/// Outcome of due date extraction. Nothing is invented: anything but `Found` needs a person.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DueDate {
+    Found {
+        date: NaiveDate,
+        /// Rule is "labelled", "net-terms" or "on-receipt"; the line is the date's
+        evidence: Evidence,
+    },
+    /// A numeric date such as 03/04/2026 that reads validly as both D/M and M/D
+    Ambiguous(Vec<NaiveDate>),
+    NotFound,
+}
+
+/// One possible seller name, with the evidence that produced it
+#[derive(Clone, Debug)]
+pub struct VendorCandidate {
+    pub name: String,
+    pub score: u32,
+    pub reasons: Vec<&'static str>,
+    /// 1-based line of the first signal that produced the name
+    pub line: usize,
+}
+
+impl VendorCandidate {
+    /// The candidate's score as a 0.0..=1.0 weight; a labelled seller block reaches 1.0.
+    pub fn weight(&self) -> f64 {
+        f64::from(self.score.min(100)) / 100.0
+    }
+}
+
+/// Everything read from the OCR text, each field with its provenance
+#[derive(Clone, Debug)]
+pub struct ExtractionResult {
+    /// Best match first; empty when no seller could be identified
+    pub vendors: Vec<VendorCandidate>,
+    pub amount: Option<Extracted<Amount>>,
+    pub due_date: DueDate,
+    pub invoice_number: Option<Extracted<String>>,
+}
+
+/// Extracts the invoice fields from OCR text. `line_confidence` is the OCR engine's
+/// confidence per line of `text`, folded into each field's evidence when present.
+pub fn parse_invoice(text: &str, line_confidence: Option<&[f64]>) -> ExtractionResult {
+    println!("\n===== PARSING INVOICE DATA =====");
+
+    let vendors = seller_candidates(text);
+    for candidate in &vendors {
+        println!(
+            "  Vendor candidate: '{}' (score {}, {}, line {})",
+            candidate.name,
+            candidate.score,
+            candidate.reasons.join(", "),
+            candidate.line
+        );
+    }
+    if vendors.is_empty() {
+        println!("  Vendor Name: not found");
+    }
+
+    let amount = extract_amount(text).map(|a| Extracted { evidence: a.evidence.with_ocr(line_confidence), ..a });
+    match &amount {
+        Some(amount) => println!("  Amount: {} ({})", amount.value, amount.evidence),
+        None => println!("  Amount: Not found"),
+    }
+
+    let invoice_number =
+        extract_invoice_number(text).map(|n| Extracted { evidence: n.evidence.with_ocr(line_confidence), ..n });
+    match &invoice_number {
+        Some(number) => println!("  Invoice Number: {} ({})", number.value, number.evidence),
+        None => println!("  Invoice Number: Not found"),
+    }
+
+    let due_date = match extract_due_date(text) {
+        DueDate::Found { date, evidence } => DueDate::Found { date, evidence: evidence.with_ocr(line_confidence) },
+        other => other,
+    };
+    match &due_date {
+        DueDate::Found { date, evidence } => println!("  Due Date: {} ({})", date, evidence),
+        DueDate::Ambiguous(dates) => println!("  Due Date: ambiguous between {:?}", dates),
+        DueDate::NotFound => println!("  Due Date: Not found"),
+    }
+
+    println!("================================\n");
+
+    ExtractionResult { vendors, amount, due_date, invoice_number }
+}
+
+/// The seller's invoice number after an "Invoice No." style label, or on the next line
+/// when the label stands alone. Must contain a digit, which rules out words like "Date".
+pub fn extract_invoice_number(text: &str) -> Option<Extracted<String>> {
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    let label_re = Regex::new(INVOICE_NUMBER_LABEL_RE).unwrap();
+    let number_re = Regex::new(r"^[A-Za-z0-9][A-Za-z0-9\-/_.]*").unwrap();
+
+    let number_at = |s: &str| {
+        let number = number_re.find(s)?.as_str().trim_end_matches(['.', '-', '/']);
+        (number.chars().any(|c| c.is_ascii_digit()) && number.len() <= MAX_INVOICE_NUMBER_LEN)
+            .then(|| number.to_string())
+    };
+
+    for (i, line) in lines.iter().enumerate() {
+        let Some(m) = label_re.find(line) else {
+            continue;
+        };
+        let rest = &line[m.end()..];
+        if !rest.is_empty() {
+            if let Some(number) = number_at(rest) {
+                return Some(Extracted { value: number, evidence: Evidence::rule("invoice-number-label", i, 0.9) });
+            }
+            continue;
+        }
+        if let Some((j, number)) = next_nonempty(&lines, i).and_then(|j| Some((j, number_at(lines[j])?))) {
+            return Some(Extracted { value: number, evidence: Evidence::rule("invoice-number-label-above", j, 0.75) });
+        }
+    }
+    None
+}
+
+/// Index of the first non-empty line after `i`.
+fn next_nonempty(lines: &[&str], i: usize) -> Option<usize> {
+    (i + 1..lines.len()).find(|&j| !lines[j].is_empty())
+}
+
+/// Reads a number written with either '.' or ',' as the decimal separator.
+///
+/// With both present the last one is the decimal separator ("1,234.56", "1.234,56").
+/// With one kind only, a single occurrence followed by exactly three digits is read as a
+/// thousands separator ("1,250", "1.234"), anything else as decimal ("40.5", "12,99").
+/// A lone leading "0" is never a thousands group, so "0.125" is decimal.
+/// Thousands groups must be three digits, which rules out dates such as "12.05.2025".
+fn parse_number(raw: &str) -> Option<(u128, u32)> {
+    let cleaned: String = raw.chars().filter(|c| !matches!(c, '\'' | '\u{a0}' | '\u{202f}')).collect();
+    let decimal_sep = match (cleaned.rfind('.'), cleaned.rfind(',')) {
+        (Some(dot), Some(comma)) => Some(if dot > comma { '.' } else { ',' }),
+        (Some(_), None) | (None, Some(_)) => {
+            let sep = if cleaned.contains('.') { '.' } else { ',' };
+            let (head, tail) = cleaned.rsplit_once(sep)?;
+            if cleaned.matches(sep).count() == 1 && (tail.len() != 3 || head == "0") {
+                Some(sep)
+            } else {
+                None
+            }
+        }
+        (None, None) => None,
+    };
+
+    let (int_part, frac_part) = match decimal_sep {
+        Some(sep) => cleaned.rsplit_once(sep)?,
+        None => (cleaned.as_str(), ""),
+    };
+    if frac_part.contains(['.', ',']) {
+        return None;
+    }
+
+    let mut groups = int_part.split(['.', ',']);
+    let first = groups.next()?;
+    if first.is_empty() || (int_part.len() > first.len() && first.len() > 3) {
+        return None;
+    }
+    let mut digits = first.to_string();
+    for group in groups {
+        if group.len() != 3 {
+            return None;
+        }
+        digits.push_str(group);
+    }
+    digits.push_str(frac_part);
+
+    Some((digits.parse().ok()?, frac_part.len() as u32))
+}
+
+/// Every amount on a line, with the currency written directly before or after it.
+fn amounts_in(line: &str) -> Vec<Amount> {
+    let number_re = Regex::new(NUMBER_RE).unwrap();
+    let before_re = Regex::new(&format!(r"(?i)({})\s*$", CURRENCY_RE)).unwrap();
+    let after_re = Regex::new(&format!(r"(?i)^\s*({})", CURRENCY_RE)).unwrap();
+
+    let mut amounts = Vec::new();
+    for m in number_re.find_iter(line) {
+        let Some((digits, scale)) = parse_number(m.as_str()) else {
+            continue;
+        };
+        let currency = before_re
+            .captures(&line[..m.start()])
+            .or_else(|| after_re.captures(&line[m.end()..]))
+            .map(|c| c[1].to_uppercase());
+        amounts.push(Amount { digits, scale, currency });
+    }
+    amounts
+}
+
+/// The payable total: the amount after the most specific label ("Amount due", "Total",
+/// ...), then "$X due", then the largest amount carrying a currency anywhere.
+pub fn extract_amount(text: &str) -> Option<Extracted<Amount>> {
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    let due_re = Regex::new(&format!(r"(?i)({})\s*{}\s*due\b", CURRENCY_RE, NUMBER_RE)).unwrap();
+
+    let mut best: Option<(u32, Extracted<Amount>)> = None;
+    let mut consider = |priority: u32, amount: Extracted<Amount>| {
+        if best.as_ref().is_none_or(|(p, _)| priority > *p) {
+            best = Some((priority, amount));
+        }
+    };
+
+    for (i, line) in lines.iter().enumerate() {
+        let lower = line.to_lowercase();
+
+        for &(label, priority) in AMOUNT_LABELS {
+            let Some(pos) = find_word(&lower, label) else {
+                continue;
+            };
+            // Generic labels also appear on tax, discount and payment-received lines
+            if priority < 100 && ["tax", "discount", "paid", "credit"].iter().any(|w| lower.contains(w)) {
+                break;
+            }
+
+            let mut source = i;
+            let mut amounts = line.get(pos + label.len()..).map(amounts_in).unwrap_or_default();
+            // Table layouts put the value on the following line
+            if amounts.is_empty()
+                && let Some(j) = next_nonempty(&lines, i)
+            {
+                source = j;
+                amounts = amounts_in(lines[j]);
+            }
+            let chosen = match amounts.iter().position(|a| a.currency.is_some()) {
+                Some(idx) => amounts.into_iter().nth(idx),
+                None => amounts.pop(),
+            };
+            if let Some(amount) = chosen {
+                // A bare number, or one in the next row, is more likely to be the wrong cell
+                let mut weight = f64::from(priority.min(95)) / 100.0;
+                if source != i {
+                    weight *= 0.9;
+                }
+                if amount.currency.is_none() {
+                    weight *= 0.9;
+                }
+                let evidence = Evidence::rule(format!("label '{}'", label), source, weight);
+                consider(priority, Extracted { value: amount, evidence });
+            }
+            break;
+        }
+
+        if let Some(m) = due_re.find(line)
+            && let Some(amount) = amounts_in(m.as_str()).into_iter().next()
+        {
+            consider(95, Extracted { value: amount, evidence: Evidence::rule("amount-due-phrase", i, 0.9) });
+        }
+    }
+
+    best.map(|(_, amount)| amount).or_else(|| {
+        lines
+            .iter()
+            .enumerate()
+            .flat_map(|(i, line)| amounts_in(line).into_iter().map(move |a| (i, a)))
+            .filter(|(_, a)| a.currency.is_some())
+            .max_by_key(|(_, a)| a.digits.saturating_mul(10u128.pow(18u32.saturating_sub(a.scale))))
+            .map(|(i, value)| Extracted { value, evidence: Evidence::rule("largest-currency-amount", i, 0.5) })
+    })
+}
+
+/// Byte offset of `word` in `haystack` where it is not part of a longer word.
+fn find_word(haystack: &str, word: &str) -> Option<usize> {
+    haystack.match_indices(word).map(|(pos, _)| pos).find(|&pos| {
+        let before = haystack[..pos].chars().next_back();
+        let after = haystack[pos + word.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// If `line` starts with `label` as a whole word, returns the rest after any ':' or '-'.
+fn strip_label<'a>(line: &'a str, label: &str) -> Option<&'a str> {
+    let lower = line.to_lowercase();
+    if !lower.starts_with(label) {
+        return None;
+    }
+    let rest = line.get(label.len()..)?;
+    if rest.chars().next().is_some_and(|c| c.is_alphanumeric()) {
+        return None;
+    }
+    Some(rest.trim_start_matches([':', '-', ' ', '\t']).trim())
+}
+
+fn is_buyer_label(line: &str) -> bool {
+    BUYER_LABELS.iter().any(|label| strip_label(line, label).is_some())
+}
+
+fn is_label(line: &str) -> bool {
+    is_buyer_label(line) || SELLER_LABELS.iter().any(|(label, _, _)| strip_label(line, label).is_some())
+}
+
+/// Whether a line could be a business or person name rather than an address, contact or title.
+fn looks_like_name(line: &str) -> bool {
+    let lower = line.to_lowercase();
+    let letters = line.chars().filter(|c| c.is_alphabetic()).count();
+    let digits = line.chars().filter(|c| c.is_ascii_digit()).count();
+
+    letters >= 2
+        && digits * 3 <= letters
+        && line.len() <= MAX_VENDOR_NAME_LEN
+        && !line.contains('@')
+        && !lower.contains("http")
+        && !lower.contains("www.")
+        && !lower.starts_with("invoice")
+        && !lower.starts_with("receipt")
+        && !lower.starts_with("page ")
+        && !lower.contains("date")
+        && !lower.contains("due")
+        && !lower.contains("total")
+        && !is_label(line)
+}
+
+/// Line indexes belonging to a buyer block: the label line and the lines under it up to a blank line.
+fn buyer_block(lines: &[&str]) -> Vec<bool> {
+    let mut in_block = vec![false; lines.len()];
+    let mut i = 0;
+    while i < lines.len() {
+        if is_buyer_label(lines[i]) {
+            in_block[i] = true;
+            let mut j = i + 1;
+            while j < lines.len() && !lines[j].is_empty() && (is_buyer_label(lines[j]) || !is_label(lines[j])) {
+                in_block[j] = true;
+                j += 1;
+            }
+            i = j;
+        } else {
+            i += 1;
+        }
+    }
+    in_block
+}
+
+/// First line of the contiguous block (at most 5 lines) ending just above `i`.
+fn block_start(lines: &[&str], i: usize) -> Option<usize> {
+    (i.saturating_sub(5)..i)
+        .rev()
+        .take_while(|&j| !lines[j].is_empty())
+        .last()
+}
+
+/// Ranks possible seller names using labelled seller blocks, remit-to and payable-to
+/// instructions, tax-ID proximity and the letterhead, never the "Bill to" customer.
+pub fn seller_candidates(text: &str) -> Vec<VendorCandidate> {
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    let buyer = buyer_block(&lines);
+    let tax_id_re = Regex::new(TAX_ID_RE).unwrap();
+    let suffix_re = Regex::new(COMPANY_SUFFIX_RE).unwrap();
+
+    let mut found: Vec<(String, u32, &'static str, usize)> = Vec::new();
+    let push = |found: &mut Vec<_>, name: &str, score: u32, reason: &'static str, line: usize| {
+        let name = name.trim().trim_end_matches([',', ':']).trim();
+        if looks_like_name(name) {
+            found.push((name.to_string(), score, reason, line + 1));
+        }
+    };
+
+    for (i, line) in lines.iter().enumerate() {
+        if buyer[i] {
+            continue;
+        }
+
+        for &(label, score, reason) in SELLER_LABELS {
+            if let Some(rest) = strip_label(line, label) {
+                if !rest.is_empty() {
+                    push(&mut found, rest, score, reason, i);
+                } else if let Some(j) = next_nonempty(&lines, i) {
+                    push(&mut found, lines[j], score, reason, j);
+                }
+                break;
+            }
+        }
+
+        // "Make checks payable to X" mid-sentence in payment instructions
+        if let Some(pos) = line.to_lowercase().find("payable to")
+            && pos > 0
+            && let Some(rest) = line.get(pos + "payable to".len()..)
+        {
+            push(&mut found, rest, 95, "payable-to", i);
+        }
+
+        // The seller's registration number closes its address block, headed by its name
+        if tax_id_re.is_match(line)
+            && let Some(j) = block_start(&lines, i).filter(|&j| !buyer[j])
+        {
+            push(&mut found, lines[j], 70, "tax-id", j);
+        }
+    }
+
+    // Letterhead: the first name-like line above any label
+    for (i, line) in lines.iter().enumerate() {
+        if buyer[i] || is_label(line) {
+            break;
+        }
+        if looks_like_name(line) {
+            push(&mut found, line, 60, "letterhead", i);
+            break;
+        }
+    }
+
+    // Last resort: the first "Firstname Lastname" outside the buyer block
+    if found.is_empty() {
+        let name_re = Regex::new(r"([A-Z][a-z]+\s+[A-Z][a-z]+)").unwrap();
+        for (i, line) in lines.iter().enumerate() {
+            if !buyer[i]
+                && let Some(m) = name_re.find(line)
+            {
+                push(&mut found, m.as_str(), 10, "capitalized-name", i);
+                break;
+            }
+        }
+    }
+
+    // Merge repeated names; each extra independent signal adds confidence
+    let mut candidates: Vec<VendorCandidate> = Vec::new();
+    for (name, score, reason, line) in found {
+        match candidates.iter_mut().find(|c| c.name.eq_ignore_ascii_case(&name)) {
+            Some(c) => {
+                if !c.reasons.contains(&reason) {
+                    c.score = c.score.max(score) + 10;
+                    c.reasons.push(reason);
+                }
+            }
+            None => candidates.push(VendorCandidate { name, score, reasons: vec![reason], line }),
+        }
+    }
+    for c in &mut candidates {
+        if suffix_re.is_match(&c.name) {
+            c.score += 5;
+        }
+    }
+
+    candidates.sort_by_key(|c| std::cmp::Reverse(c.score));
+    candidates
+}
+
+/// Unix timestamp of the last second of `date` in UTC, so an invoice stays payable all day.
+pub fn end_of_day(date: NaiveDate) -> i64 {
+    date.and_hms_opt(23, 59, 59).unwrap().and_utc().timestamp()
+}
+
+/// A date as written; numeric day/month pairs may still need an order to resolve.
+#[derive(Clone, Copy, Debug)]
+enum WrittenDate {
+    Exact(NaiveDate),
+    /// Both leading numbers are 12 or less: (first, second, year)
+    DayOrMonthFirst(u32, u32, i32),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum DateOrder {
+    DayFirst,
+    MonthFirst,
+}
+
+fn month_number(name: &str) -> Option<u32> {
+    let prefix = name.get(..3)?.to_lowercase();
+    let months = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+    months.iter().position(|m| *m == prefix).map(|i| i as u32 + 1)
+}
+
+fn full_year(year: &str) -> Option<i32> {
+    let y: i32 = year.parse().ok()?;
+    Some(if year.len() == 2 { 2000 + y } else { y })
+}
+
+/// Dates on a line in ISO, numeric D/M/Y or M/D/Y, and spelled-out or abbreviated month forms.
+fn dates_in(line: &str) -> (Vec<WrittenDate>, Option<DateOrder>) {
+    let iso_re = Regex::new(r"\b(\d{4})[-/.](\d{1,2})[-/.](\d{1,2})\b").unwrap();
+    let numeric_re = Regex::new(r"\b(\d{1,2})[-/.](\d{1,2})[-/.](\d{4}|\d{2})\b").unwrap();
+    let month_first_re = Regex::new(&format!(r"(?i)\b({})\.?\s+(\d{{1,2}})(?:st|nd|rd|th)?,?\s+(\d{{4}})\b", MONTHS_RE)).unwrap();
+    let day_first_re = Regex::new(&format!(r"(?i)\b(\d{{1,2}})(?:st|nd|rd|th)?[\s-]+({})\.?,?[\s-]+(\d{{4}})\b", MONTHS_RE)).unwrap();
+
+    let mut found: Vec<(usize, WrittenDate)> = Vec::new();
+    let mut taken: Vec<(usize, usize)> = Vec::new();
+    let mut order = None;
+    let mut claim = |start: usize, end: usize| {
+        if taken.iter().any(|&(s, e)| start < e && s < end) {
+            return false;
+        }
+        taken.push((start, end));
+        true
+    };
+
+    for caps in iso_re.captures_iter(line) {
+        let m = caps.get(0).unwrap();
+        if let (Some(y), Ok(mo), Ok(d)) = (full_year(&caps[1]), caps[2].parse(), caps[3].parse())
+            && let Some(date) = NaiveDate::from_ymd_opt(y, mo, d)
+            && claim(m.start(), m.end())
+        {
+            found.push((m.start(), WrittenDate::Exact(date)));
+        }
+    }
+    for caps in month_first_re.captures_iter(line) {
+        let m = caps.get(0).unwrap();
+        if let (Some(mo), Ok(d), Some(y)) = (month_number(&caps[1]), caps[2].parse(), full_year(&caps[3]))
+            && let Some(date) = NaiveDate::from_ymd_opt(y, mo, d)
+            && claim(m.start(), m.end())
+        {
+            found.push((m.start(), WrittenDate::Exact(date)));
+        }
+    }
+    for caps in day_first_re.captures_iter(line) {
+        let m = caps.get(0).unwrap();
+        if let (Ok(d), Some(mo), Some(y)) = (caps[1].parse(), month_number(&caps[2]), full_year(&caps[3]))
+            && let Some(date) = NaiveDate::from_ymd_opt(y, mo, d)
+            && claim(m.start(), m.end())
+        {
+            found.push((m.start(), WrittenDate::Exact(date)));
+        }
+    }
+    for caps in numeric_re.captures_iter(line) {
+        let m = caps.get(0).unwrap();
+        let (Ok(a), Ok(b), Some(y)) = (caps[1].parse::<u32>(), caps[2].parse::<u32>(), full_year(&caps[3])) else {
+            continue;
+        };
+        let written = if a > 12 {
+            order = Some(DateOrder::DayFirst);
+            NaiveDate::from_ymd_opt(y, b, a).map(WrittenDate::Exact)
+        } else if b > 12 {
+            order = Some(DateOrder::MonthFirst);
+            NaiveDate::from_ymd_opt(y, a, b).map(WrittenDate::Exact)
+        } else if a == b {
+            NaiveDate::from_ymd_opt(y, a, b).map(WrittenDate::Exact)
+        } else {
+            Some(WrittenDate::DayOrMonthFirst(a, b, y))
+        };
+        if let Some(written) = written
+            && claim(m.start(), m.end())
+        {
+            found.push((m.start(), written));
+        }
+    }
+
+    found.sort_by_key(|(start, _)| *start);
+    (found.into_iter().map(|(_, d)| d).collect(), order)
+}
+
+/// Every reading of `date`, narrowed by the document's D/M or M/D convention when known.
+fn readings(date: WrittenDate, order: Option<DateOrder>) -> Vec<NaiveDate> {
+    match date {
+        WrittenDate::Exact(date) => vec![date],
+        WrittenDate::DayOrMonthFirst(a, b, y) => {
+            let day_first = NaiveDate::from_ymd_opt(y, b, a);
+            let month_first = NaiveDate::from_ymd_opt(y, a, b);
+            match order {
+                Some(DateOrder::DayFirst) => day_first.into_iter().collect(),
+                Some(DateOrder::MonthFirst) => month_first.into_iter().collect(),
+                None => day_first.into_iter().chain(month_first).collect(),
+            }
+        }
+    }
+}
+
+fn resolve(dates: Vec<NaiveDate>, evidence: Evidence) -> DueDate {
+    match dates.as_slice() {
+        [] => DueDate::NotFound,
+        [date] => DueDate::Found { date: *date, evidence },
+        _ => DueDate::Ambiguous(dates),
+    }
+}
+
+/// The first date on the line labelled by `label_re`, or on the next non-empty line when
+/// the label stands alone (table layouts), with the index of the line it was read from.
+fn labelled_date(lines: &[&str], label_re: &Regex, skip: &[&Regex]) -> Option<(usize, WrittenDate)> {
+    for (i, line) in lines.iter().enumerate() {
+        let Some(m) = label_re.find(line) else {
+            continue;
+        };
+        if skip.iter().any(|re| re.is_match(line)) {
+            continue;
+        }
+        // Dates before the label belong to it too ("$40.00 due March 3, 2026" reads left to right)
+        let (dates, _) = dates_in(&line[m.start()..]);
+        let dates = if dates.is_empty() { dates_in(line).0 } else { dates };
+        if let Some(date) = dates.first() {
+            return Some((i, *date));
+        }
+        // Anything after the label ("Payment due: see terms") is the value, not a heading
+        if line[m.end()..].trim_matches([':', '-', ' ', '\t']).is_empty()
+            && let Some(j) = next_nonempty(lines, i)
+            && let Some(date) = dates_in(lines[j]).0.first()
+        {
+            return Some((j, *date));
+        }
+    }
+    None
+}
+
+/// Due date from an explicitly labelled date, else payment terms ("Net 30", "due on
+/// receipt") counted from the issue date. Ambiguous numeric dates are reported, not guessed.
+pub fn extract_due_date(text: &str) -> DueDate {
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+
+    // A D/M or M/D date elsewhere in the document settles the convention for the rest
+    let order = lines.iter().find_map(|line| dates_in(line).1);
+
+    let receipt_re = Regex::new(r"(?i)\b(due|payable)\s+(on|upon)\s+receipt\b").unwrap();
+    let due_re = Regex::new(r"(?i)\b(due\s+date|date\s+due|payment\s+due|due|pay\s+by|payment\s+by)\b").unwrap();
+    // "Amount due" and "Balance due" label the total; the date under them is the issue date
+    let amount_due_re = Regex::new(r"(?i)\b(amount|balance|total)\s+due\b").unwrap();
+    if let Some((line, date)) = labelled_date(&lines, &due_re, &[&receipt_re, &amount_due_re]) {
+        return resolve(readings(date, order), Evidence::rule("labelled", line, 0.95));
+    }
+
+    let net_re = Regex::new(r"(?i)\bnet\s*-?\s*(\d{1,3})\b|\bwithin\s+(\d{1,3})\s+days\b|\b(\d{1,3})\s+days\s+net\b").unwrap();
+    let terms = lines.iter().find_map(|line| {
+        if receipt_re.is_match(line) {
+            return Some((0, "on-receipt", 0.8));
+        }
+        let caps = net_re.captures(line)?;
+        let days = caps.iter().skip(1).flatten().next()?.as_str().parse::<i64>().ok()?;
+        Some((days, "net-terms", 0.85))
+    });
+    let Some((days, source, mut weight)) = terms else {
+        return DueDate::NotFound;
+    };
+
+    let issue_re = Regex::new(r"(?i)\b(invoice\s+date|issue\s+date|date\s+of\s+issue|issued|dated|date)\b").unwrap();
+    let issue = labelled_date(&lines, &issue_re, &[&due_re]).or_else(|| {
+        // A lone date on a document with terms can only be the issue date
+        let all: Vec<(usize, WrittenDate)> = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(i, line)| dates_in(line).0.into_iter().map(move |d| (i, d)))
+            .collect();
+        match all.as_slice() {
+            [only] => {
+                weight *= 0.8;
+                Some(*only)
+            }
+            _ => None,
+        }
+    });
+    let Some((line, issue)) = issue else {
+        return DueDate::NotFound;
+    };
+
+    let due: Vec<NaiveDate> = readings(issue, order)
+        .into_iter()
+        .filter_map(|d| d.checked_add_signed(chrono::Duration::days(days)))
+        .collect();
+    resolve(due, Evidence::rule(source, line, weight))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(digits: u128, scale: u32, currency: Option<&str>) -> Amount {
+        Amount { digits, scale, currency: currency.map(str::to_string) }
+    }
+
+    #[test]
+    fn parse_number_reads_locale_separators() {
+        assert_eq!(parse_number("1,250.00"), Some((125000, 2)));
+        assert_eq!(parse_number("1.234,56"), Some((123456, 2)));
+        assert_eq!(parse_number("1'234.50"), Some((123450, 2)));
+        assert_eq!(parse_number("1\u{202f}234,50"), Some((123450, 2)));
+        assert_eq!(parse_number("1,250"), Some((1250, 0)));
+        assert_eq!(parse_number("1.234"), Some((1234, 0)));
+        assert_eq!(parse_number("40.5"), Some((405, 1)));
+        assert_eq!(parse_number("12,99"), Some((1299, 2)));
+        assert_eq!(parse_number("1,234,567"), Some((1234567, 0)));
+    }
+
+    #[test]
+    fn parse_number_reads_a_leading_zero_as_decimal() {
+        assert_eq!(parse_number("0.125"), Some((125, 3)));
+        assert_eq!(parse_number("0,125"), Some((125, 3)));
+        assert_eq!(parse_number("0.50"), Some((50, 2)));
+    }
+
+    #[test]
+    fn parse_number_rejects_dates_and_bad_groups() {
+        assert_eq!(parse_number("12.05.2025"), None);
+        assert_eq!(parse_number("1,23,456"), None);
+        assert_eq!(parse_number("1234,567.00"), None);
+    }
+
+    #[test]
+    fn amount_prefers_amount_due_over_subtotal_and_tax() {
+        let text = "Subtotal: $1,100.00\nTax (10%): $110.00\nTotal: $1,210.00\nPaid: $10.00\nAmount due: $1,200.00";
+        let found = extract_amount(text).unwrap();
+        assert_eq!(found.value, amount(120000, 2, Some("$")));
+        assert_eq!(found.evidence.line, Some(5));
+        assert_eq!(found.value.to_base_units(6), Some(1_200_000_000));
+    }
+
+    #[test]
+    fn amount_in_table_row_below_the_label() {
+        let text = "Description        Qty   Price\nConsulting         10    120,00\n\nGesamtbetrag\nTotal EUR\n1.200,00 EUR";
+        let found = extract_amount(text).unwrap();
+        assert_eq!(found.value, amount(120000, 2, Some("EUR")));
+        assert_eq!(found.evidence.line, Some(6));
+    }
+
+    #[test]
+    fn amount_falls_back_to_largest_currency_amount() {
+        let found = extract_amount("Widgets x2  $12.50\nShipping  $4.00\nThank you!").unwrap();
+        assert_eq!(found.value, amount(1250, 2, Some("$")));
+        assert_eq!(found.evidence.rule, "largest-currency-amount");
+    }
+
+    fn due(text: &str) -> (NaiveDate, String) {
+        match extract_due_date(text) {
+            DueDate::Found { date, evidence } => (date, evidence.rule),
+            other => panic!("expected a due date, got {:?}", other),
+        }
+    }
+
+    fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn amount_due_label_is_not_a_due_date() {
+        let text = "ACME Corp\nAmount due: $40.00\nInvoice date: 2026-01-02\nTerms: Net 30";
+        assert_eq!(due(text), (ymd(2026, 2, 1), "net-terms".to_string()));
+
+        let text = "ACME Corp\nBalance Due\n$40.00\nDate: 2026-01-02\nDue on receipt";
+        assert_eq!(due(text), (ymd(2026, 1, 2), "on-receipt".to_string()));
+    }
+
+    #[test]
+    fn due_date_label_in_each_written_form() {
+        assert_eq!(due("Invoice date: 2026-01-02\nDue date: 2026-02-15").0, ymd(2026, 2, 15));
+        assert_eq!(due("Payment due: March 3, 2026").0, ymd(2026, 3, 3));
+        assert_eq!(due("Pay by 3rd Mar 2026").0, ymd(2026, 3, 3));
+        assert_eq!(due("$40.00 due 15/03/2026").0, ymd(2026, 3, 15));
+    }
+
+    #[test]
+    fn due_date_in_the_row_below_a_lone_label() {
+        let text = "Invoice Date: 2026-01-02\nDue Date\n2026-02-01\nAmount due: $40.00";
+        assert_eq!(due(text), (ymd(2026, 2, 1), "labelled".to_string()));
+        // A label with its own text does not borrow the next row's date
+        let text = "Payment due: see terms\nDated 2026-01-02\nNet 14";
+        assert_eq!(due(text), (ymd(2026, 1, 16), "net-terms".to_string()));
+    }
+
+    #[test]
+    fn numeric_due_dates_use_the_documents_convention() {
+        // 25/12/2025 can only be D/M, which settles 03/04/2026 as 3 April
+        assert_eq!(due("Date: 25/12/2025\nDue date: 03/04/2026").0, ymd(2026, 4, 3));
+        assert_eq!(
+            extract_due_date("Due date: 03/04/2026"),
+            DueDate::Ambiguous(vec![ymd(2026, 4, 3), ymd(2026, 3, 4)])
+        );
+        assert_eq!(extract_due_date("Thank you for your business"), DueDate::NotFound);
+    }
+
+    fn names(text: &str) -> Vec<String> {
+        seller_candidates(text).into_iter().map(|c| c.name).collect()
+    }
+
+    #[test]
+    fn letterhead_backed_by_tax_id_outranks_bill_to() {
+        let text = "Northwind Traders Ltd\n12 Harbour Road\nLeeds LS1 4AB\nVAT No: GB123456789\n\n\
+                    INVOICE\nInvoice No: INV-1042\nDate: 2026-01-02\n\n\
+                    Bill To:\nContoso Inc\n1 Main Street\nSpringfield\n\nTotal: £1,250.00";
+        let candidates = seller_candidates(text);
+        let top = &candidates[0];
+        assert_eq!(top.name, "Northwind Traders Ltd");
+        assert_eq!(top.line, 1);
+        assert!(top.reasons.contains(&"letterhead") && top.reasons.contains(&"tax-id"));
+        assert!(!names(text).contains(&"Contoso Inc".to_string()));
+    }
+
+    #[test]
+    fn remit_to_block_outranks_letterhead() {
+        let text = "INVOICE\nAcme Holdings\n\nBill to: Globex Corporation\n742 Evergreen Terrace\n\n\
+                    Amount due: $40.00\n\nRemit payment to:\nAcme Payments LLC\nPO Box 100";
+        let candidates = seller_candidates(text);
+        assert_eq!(candidates[0].name, "Acme Payments LLC");
+        assert_eq!(candidates[0].reasons, vec!["remit-to"]);
+        assert_eq!(candidates[0].line, 10);
+        assert_eq!(candidates[1].name, "Acme Holdings");
+        assert!(!names(text).contains(&"Globex Corporation".to_string()));
+    }
+
+    #[test]
+    fn payable_to_in_payment_instructions() {
+        let text = "Bill to:\nJane Smith\n42 Elm Street\n\nInvoice # 1001\nPlease make checks payable to Blue Ridge Plumbing";
+        assert_eq!(names(text), vec!["Blue Ridge Plumbing"]);
+    }
+
+    #[test]
+    fn buyer_only_document_has_no_buyer_candidate() {
+        let text = "Bill to:\nJane Smith\n42 Elm Street\n\nInvoice 1001\nThanks, Bob Jones";
+        let candidates = seller_candidates(text);
+        assert_eq!(names(text), vec!["Bob Jones"]);
+        assert_eq!(candidates[0].reasons, vec!["capitalized-name"]);
+    }
+}
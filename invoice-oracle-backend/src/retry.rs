@@ -0,0 +1,227 @@
+use std::time::Duration;
+
+use invoice_claim_client::{AnchorErrorCode, InvoiceError};
+use rand::Rng;
+use solana_client::client_error::ClientError;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::transaction::TransactionError;
+
+use crate::config::RetryConfig;
+use crate::ocr::OcrError;
+use crate::store::Rejection;
+
+/// Whether a failed extraction is worth retrying
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ErrorClass {
+    /// RPC hiccups, HTTP 5xx/429, expired blockhashes, a paused org
+    Transient,
+    /// The program rejected the result outright (unregistered vendor, CapExceeded, ...),
+    /// or the document cannot be read
+    Permanent,
+}
+
+pub fn classify(error: &(dyn std::error::Error + 'static)) -> ErrorClass {
+    if let Some(e) = error.downcast_ref::<OcrError>() {
+        return match e {
+            OcrError::Unreadable(_) => ErrorClass::Permanent,
+            OcrError::Failed(cause) => classify(cause.as_ref()),
+        };
+    }
+
+    if let Some(e) = error.downcast_ref::<ClientError>() {
+        return match e.get_transaction_error() {
+            Some(tx_error) => classify_transaction_error(&tx_error),
+            // Transport, timeout and RPC node errors
+            None => ErrorClass::Transient,
+        };
+    }
+
+    if let Some(e) = error.downcast_ref::<reqwest::Error>() {
+        return match e.status() {
+            Some(status) if status.is_client_error() && status.as_u16() != 429 => ErrorClass::Permanent,
+            _ => ErrorClass::Transient,
+        };
+    }
+
+    ErrorClass::Transient
+}
+
+fn classify_transaction_error(error: &TransactionError) -> ErrorClass {
+    match error {
+        TransactionError::BlockhashNotFound
+        | TransactionError::AccountInUse
+        | TransactionError::WouldExceedMaxBlockCostLimit
+        | TransactionError::WouldExceedMaxAccountCostLimit
+        | TransactionError::InsufficientFundsForFee => ErrorClass::Transient,
+        TransactionError::InstructionError(_, InstructionError::Custom(code))
+            if *code == u32::from(InvoiceError::OrgPaused) =>
+        {
+            ErrorClass::Transient
+        }
+        // e.g. AccountNotInitialized for an unregistered vendor PDA, CapExceeded,
+        // InvalidDueDate, or the invoice PDA already existing
+        _ => ErrorClass::Permanent,
+    }
+}
+
+/// The on-chain rejection a permanent error calls for, if the submitter is the one
+/// who can fix it.
+pub fn rejection(error: &(dyn std::error::Error + 'static)) -> Option<Rejection> {
+    // The gateway or OCR service refused the document, or the engine could not read it
+    if error.is::<OcrError>() {
+        return Some(Rejection::OcrFailure);
+    }
+
+    let tx_error = error.downcast_ref::<ClientError>()?.get_transaction_error()?;
+    let TransactionError::InstructionError(_, InstructionError::Custom(code)) = tx_error else {
+        return None;
+    };
+    // AccountNotInitialized: no VendorAccount PDA for the submitted name
+    if code == u32::from(AnchorErrorCode::AccountNotInitialized) {
+        return Some(Rejection::UnknownVendor);
+    }
+    let rejections = [
+        (InvoiceError::CapExceeded, Rejection::CapExceeded),
+        (InvoiceError::InvalidDueDate, Rejection::InvalidDueDate),
+        (InvoiceError::DuplicateInvoice, Rejection::Duplicate),
+        (InvoiceError::InvalidVendor, Rejection::UnknownVendor),
+        (InvoiceError::VendorInactive, Rejection::UnknownVendor),
+        // No usable total came out of the document
+        (InvoiceError::InvalidAmount, Rejection::OcrFailure),
+    ];
+    rejections
+        .into_iter()
+        .find(|(error, _)| u32::from(*error) == code)
+        .map(|(_, rejection)| rejection)
+}
+
+/// Per-request retry schedule: exponential backoff with jitter, capped by `max_attempts`.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Fraction of the delay randomly added or removed, 0.0..=1.0
+    pub jitter: f64,
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &RetryConfig) -> Self {
+        RetryPolicy {
+            max_attempts: config.max_attempts,
+            base_delay: Duration::from_secs(config.base_delay_secs),
+            max_delay: Duration::from_secs(config.max_delay_secs),
+            jitter: config.jitter,
+        }
+    }
+
+    /// Delay before the attempt following `attempts` failed ones.
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let exp = attempts.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay
+            .saturating_mul(1u32 << exp)
+            .min(self.max_delay);
+
+        if self.jitter <= 0.0 {
+            return delay;
+        }
+        let factor = 1.0 + rand::thread_rng().gen_range(-self.jitter..=self.jitter);
+        delay.mul_f64(factor)
+    }
+
+    pub fn should_dead_letter(&self, class: ErrorClass, attempts: u32) -> bool {
+        class == ErrorClass::Permanent || attempts >= self.max_attempts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// The error reqwest reports for a response with `status`, served from a local socket.
+    async fn http_error(status: u16) -> reqwest::Error {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/ipfs/QmTest", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).await;
+            let response = format!("HTTP/1.1 {} Status\r\ncontent-length: 0\r\nconnection: close\r\n\r\n", status);
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        reqwest::get(&url).await.unwrap().error_for_status().unwrap_err()
+    }
+
+    fn program_error(code: impl Into<u32>) -> Box<dyn std::error::Error> {
+        let tx_error = TransactionError::InstructionError(0, InstructionError::Custom(code.into()));
+        Box::new(ClientError::from(tx_error))
+    }
+
+    #[test]
+    fn program_errors_map_to_rejections() {
+        let cases = [
+            (InvoiceError::CapExceeded, Some(Rejection::CapExceeded)),
+            (InvoiceError::InvalidDueDate, Some(Rejection::InvalidDueDate)),
+            (InvoiceError::DuplicateInvoice, Some(Rejection::Duplicate)),
+            (InvoiceError::InvalidVendor, Some(Rejection::UnknownVendor)),
+            (InvoiceError::VendorInactive, Some(Rejection::UnknownVendor)),
+            (InvoiceError::InvalidAmount, Some(Rejection::OcrFailure)),
+            (InvoiceError::Unauthorized, None),
+            (InvoiceError::InvalidInvoiceNumber, None),
+        ];
+        for (error, expected) in cases {
+            let error = program_error(error);
+            assert_eq!(classify(error.as_ref()), ErrorClass::Permanent);
+            assert_eq!(rejection(error.as_ref()), expected, "{}", error);
+        }
+
+        let missing_vendor = program_error(AnchorErrorCode::AccountNotInitialized);
+        assert_eq!(rejection(missing_vendor.as_ref()), Some(Rejection::UnknownVendor));
+    }
+
+    #[test]
+    fn paused_org_and_rpc_hiccups_are_retried() {
+        let paused = program_error(InvoiceError::OrgPaused);
+        assert_eq!(classify(paused.as_ref()), ErrorClass::Transient);
+        assert_eq!(rejection(paused.as_ref()), None);
+
+        let expired: Box<dyn std::error::Error> = Box::new(ClientError::from(TransactionError::BlockhashNotFound));
+        assert_eq!(classify(expired.as_ref()), ErrorClass::Transient);
+    }
+
+    fn ocr_failed(error: impl std::error::Error + 'static) -> Box<dyn std::error::Error> {
+        OcrError::from_provider(Box::new(error)).into()
+    }
+
+    #[tokio::test]
+    async fn gateway_4xx_rejects_as_ocr_failure() {
+        let error = ocr_failed(http_error(404).await);
+        assert_eq!(classify(error.as_ref()), ErrorClass::Permanent);
+        assert_eq!(rejection(error.as_ref()), Some(Rejection::OcrFailure));
+    }
+
+    #[tokio::test]
+    async fn rate_limits_and_5xx_are_retried() {
+        for status in [429, 502, 503] {
+            let error = ocr_failed(http_error(status).await);
+            assert_eq!(classify(error.as_ref()), ErrorClass::Transient, "HTTP {}", status);
+        }
+    }
+
+    #[test]
+    fn unreadable_documents_reject_as_ocr_failure() {
+        let error: Box<dyn std::error::Error> =
+            OcrError::from_provider(OcrError::Unreadable("OCR processing failed".to_string()).into()).into();
+        assert!(matches!(error.downcast_ref::<OcrError>(), Some(OcrError::Unreadable(_))));
+        assert_eq!(classify(error.as_ref()), ErrorClass::Permanent);
+        assert_eq!(rejection(error.as_ref()), Some(Rejection::OcrFailure));
+    }
+
+    #[test]
+    fn engine_errors_are_retried() {
+        let error = ocr_failed(std::io::Error::other("tesseract exited with 1"));
+        assert_eq!(classify(error.as_ref()), ErrorClass::Transient);
+    }
+}
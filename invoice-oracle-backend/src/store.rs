@@ -0,0 +1,468 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use clap::ValueEnum;
+use invoice_claim_client::RejectReason;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::parser::Evidence;
+
+/// Lifecycle of a single InvoiceRequest as seen by this oracle
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum JobStage {
+    Discovered,
+    OcrDone,
+    Parsed,
+    Submitted,
+    Confirmed,
+    /// Seller name did not confidently match a registered vendor; waits for an operator
+    NeedsReview,
+    /// Failed but scheduled for another attempt at `next_retry_at`
+    Failed,
+    /// Gave up: permanent error or out of attempts. Only an operator requeue revives it.
+    DeadLettered,
+    /// The request was marked Failed on-chain via reject_extraction
+    Rejected,
+}
+
+/// Why a request is rejected on-chain; mirrors the program's `RejectReason`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize, ValueEnum)]
+pub enum Rejection {
+    OcrFailure,
+    UnknownVendor,
+    CapExceeded,
+    InvalidDueDate,
+    Duplicate,
+}
+
+impl From<Rejection> for RejectReason {
+    fn from(rejection: Rejection) -> Self {
+        match rejection {
+            Rejection::OcrFailure => RejectReason::OcrFailure,
+            Rejection::UnknownVendor => RejectReason::UnknownVendor,
+            Rejection::CapExceeded => RejectReason::CapExceeded,
+            Rejection::InvalidDueDate => RejectReason::InvalidDueDate,
+            Rejection::Duplicate => RejectReason::Duplicate,
+        }
+    }
+}
+
+/// Fields extracted from the OCR text, kept so a resubmission never re-parses
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ParsedInvoice {
+    /// Seller chosen from `vendor_candidates`
+    pub vendor: String,
+    /// Ranked seller names found in the document, best first
+    pub vendor_candidates: Vec<String>,
+    /// Similarity between the document and `vendor`; 1.0 once an operator approved it
+    pub vendor_score: f64,
+    /// Other registered vendors that match the document as well as `vendor`
+    pub vendor_ties: Vec<String>,
+    pub amount: u64,
+    /// 0 until resolved from the document or by an operator
+    pub due_date: i64,
+    /// Possible due dates when the document's date was ambiguous
+    pub due_date_candidates: Vec<i64>,
+    /// Empty until found in the document or supplied by an operator
+    pub invoice_number: String,
+    pub evidence: FieldEvidence,
+}
+
+impl ParsedInvoice {
+    /// Overall confidence in basis points, as submitted on-chain.
+    pub fn confidence_bps(&self) -> u16 {
+        (self.evidence.confidence().clamp(0.0, 1.0) * 10_000.0).round() as u16
+    }
+}
+
+/// Provenance of each `ParsedInvoice` field; None where nothing was found
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FieldEvidence {
+    pub vendor: Option<Evidence>,
+    pub amount: Option<Evidence>,
+    pub due_date: Option<Evidence>,
+    pub invoice_number: Option<Evidence>,
+}
+
+impl FieldEvidence {
+    /// The weakest field's confidence; a field without evidence counts as 0, so nothing
+    /// unaccounted for is ever submitted as certain.
+    pub fn confidence(&self) -> f64 {
+        [&self.vendor, &self.amount, &self.due_date, &self.invoice_number]
+            .into_iter()
+            .map(|e| e.as_ref().map_or(0.0, |e| e.confidence))
+            .fold(1.0, f64::min)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Job {
+    pub request: String,
+    pub request_timestamp: i64,
+    /// OrgConfig the request was submitted to
+    pub org: String,
+    pub stage: JobStage,
+    pub attempts: u32,
+    /// Cached OCR output; once present the provider is never called again for this job
+    pub ocr_text: Option<String>,
+    /// Per-line OCR confidence of `ocr_text`, when the provider reported it
+    pub ocr_line_confidence: Option<Vec<f64>>,
+    pub parsed: Option<ParsedInvoice>,
+    /// Signature of the last process_extraction_result transaction sent
+    pub signature: Option<String>,
+    pub last_error: Option<String>,
+    pub next_retry_at: Option<i64>,
+    /// Set once the request is known to be unprocessable; attempts then submit the rejection
+    pub rejection: Option<Rejection>,
+    /// The extracted amount is outside the org's tolerance of the claimed one
+    pub amount_mismatch: bool,
+    /// Submitted below the org's minimum extraction confidence
+    pub low_confidence: bool,
+    pub updated_at: i64,
+}
+
+/// Per-org job counts reported by `JobStore::org_stats`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OrgStats {
+    pub confirmed: u64,
+    pub rejected: u64,
+    pub needs_review: u64,
+    pub dead_lettered: u64,
+    /// Not yet landed: still being worked on or waiting for a retry
+    pub in_flight: u64,
+    pub recent_confirmed: u64,
+    pub recent_rejected: u64,
+    /// Confirmed invoices sent to audit because the claimed amount disagreed
+    pub amount_mismatches: u64,
+    /// Confirmed invoices sent to audit for low extraction confidence
+    pub low_confidence: u64,
+}
+
+/// Embedded sled database of oracle jobs, keyed by request pubkey and creation time
+/// so a closed and re-created request PDA is treated as a new job.
+pub struct JobStore {
+    db: sled::Db,
+}
+
+impl JobStore {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(JobStore { db: sled::open(path)? })
+    }
+
+    fn key(request: &Pubkey, request_timestamp: i64) -> String {
+        format!("{}:{}", request, request_timestamp)
+    }
+
+    pub fn get(&self, request: &Pubkey, request_timestamp: i64) -> Result<Option<Job>, Box<dyn std::error::Error>> {
+        match self.db.get(Self::key(request, request_timestamp))? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Loads the job for a request, recording it as Discovered on first sight.
+    pub fn get_or_discover(
+        &self,
+        request: &Pubkey,
+        request_timestamp: i64,
+        org: &Pubkey,
+    ) -> Result<Job, Box<dyn std::error::Error>> {
+        if let Some(job) = self.get(request, request_timestamp)? {
+            return Ok(job);
+        }
+
+        let job = Job {
+            request: request.to_string(),
+            request_timestamp,
+            org: org.to_string(),
+            stage: JobStage::Discovered,
+            attempts: 0,
+            ocr_text: None,
+            ocr_line_confidence: None,
+            parsed: None,
+            signature: None,
+            last_error: None,
+            next_retry_at: None,
+            rejection: None,
+            amount_mismatch: false,
+            low_confidence: false,
+            updated_at: chrono::Utc::now().timestamp(),
+        };
+        self.save(&job)?;
+        Ok(job)
+    }
+
+    pub fn save(&self, job: &Job) -> Result<(), Box<dyn std::error::Error>> {
+        let mut job = job.clone();
+        job.updated_at = chrono::Utc::now().timestamp();
+
+        let request = job.request.parse::<Pubkey>()?;
+        self.db.insert(Self::key(&request, job.request_timestamp), serde_json::to_vec(&job)?)?;
+        // Flush every transition so a crash never loses a Submitted signature
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Moves the job to `stage`, clearing any previous error.
+    pub fn advance(&self, job: &mut Job, stage: JobStage) -> Result<(), Box<dyn std::error::Error>> {
+        job.stage = stage;
+        job.last_error = None;
+        self.save(job)?;
+        println!("Job {} -> {:?}", job.request, stage);
+        Ok(())
+    }
+
+    /// Records a retryable failure; the job becomes due again at `retry_at`.
+    pub fn fail(&self, job: &mut Job, error: &str, retry_at: i64) -> Result<(), Box<dyn std::error::Error>> {
+        job.stage = JobStage::Failed;
+        job.last_error = Some(error.to_string());
+        job.next_retry_at = Some(retry_at);
+        self.save(job)?;
+        println!("Job {} -> Failed ({}), retry at {}", job.request, error, retry_at);
+        Ok(())
+    }
+
+    pub fn dead_letter(&self, job: &mut Job, error: &str) -> Result<(), Box<dyn std::error::Error>> {
+        job.stage = JobStage::DeadLettered;
+        job.last_error = Some(error.to_string());
+        job.next_retry_at = None;
+        self.save(job)?;
+        println!("Job {} -> DeadLettered ({})", job.request, error);
+        Ok(())
+    }
+
+    /// Records that the job's rejection landed; `last_error` keeps the reason's detail.
+    pub fn reject(&self, job: &mut Job) -> Result<(), Box<dyn std::error::Error>> {
+        job.stage = JobStage::Rejected;
+        job.next_retry_at = None;
+        self.save(job)?;
+        println!("Job {} -> Rejected ({:?})", job.request, job.rejection);
+        Ok(())
+    }
+
+    /// Parks the job until an operator approves a vendor for it.
+    pub fn review(&self, job: &mut Job, reason: &str) -> Result<(), Box<dyn std::error::Error>> {
+        job.stage = JobStage::NeedsReview;
+        job.last_error = Some(reason.to_string());
+        job.next_retry_at = None;
+        self.save(job)?;
+        println!("Job {} -> NeedsReview ({})", job.request, reason);
+        Ok(())
+    }
+
+    fn jobs(&self) -> impl Iterator<Item = Result<Job, Box<dyn std::error::Error>>> + '_ {
+        self.db.iter().values().map(|bytes| Ok(serde_json::from_slice(&bytes?)?))
+    }
+
+    pub fn in_stage(&self, stage: JobStage) -> Result<Vec<Job>, Box<dyn std::error::Error>> {
+        let mut jobs = Vec::new();
+        for job in self.jobs() {
+            let job = job?;
+            if job.stage == stage {
+                jobs.push(job);
+            }
+        }
+        Ok(jobs)
+    }
+
+    /// Job counts per org, by outcome; `since` bounds the `recent_*` counters by last update.
+    pub fn org_stats(&self, since: i64) -> Result<BTreeMap<String, OrgStats>, Box<dyn std::error::Error>> {
+        let mut stats: BTreeMap<String, OrgStats> = BTreeMap::new();
+        for job in self.jobs() {
+            let job = job?;
+            let entry = stats.entry(job.org.clone()).or_default();
+            let recent = job.updated_at >= since;
+            match job.stage {
+                JobStage::Confirmed => {
+                    entry.confirmed += 1;
+                    entry.recent_confirmed += u64::from(recent);
+                    entry.amount_mismatches += u64::from(job.amount_mismatch);
+                    entry.low_confidence += u64::from(job.low_confidence);
+                }
+                JobStage::Rejected => {
+                    entry.rejected += 1;
+                    entry.recent_rejected += u64::from(recent);
+                }
+                JobStage::NeedsReview => entry.needs_review += 1,
+                JobStage::DeadLettered => entry.dead_lettered += 1,
+                JobStage::Discovered
+                | JobStage::OcrDone
+                | JobStage::Parsed
+                | JobStage::Submitted
+                | JobStage::Failed => entry.in_flight += 1,
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Failed jobs whose backoff has elapsed.
+    pub fn due_retries(&self, now: i64) -> Result<Vec<Job>, Box<dyn std::error::Error>> {
+        let mut jobs = Vec::new();
+        for job in self.jobs() {
+            let job = job?;
+            if job.stage == JobStage::Failed && job.next_retry_at.is_some_and(|at| at <= now) {
+                jobs.push(job);
+            }
+        }
+        Ok(jobs)
+    }
+
+    /// Puts every dead-lettered job for `request` back in the retry queue with a fresh
+    /// attempt budget. Cached OCR text is kept so no OCR credits are spent again, but the
+    /// parse is redone so a vendor registered since is picked up.
+    pub fn requeue(&self, request: &Pubkey) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut requeued = 0;
+        for entry in self.db.scan_prefix(format!("{}:", request)) {
+            let (_, bytes) = entry?;
+            let mut job: Job = serde_json::from_slice(&bytes)?;
+            if job.stage != JobStage::DeadLettered {
+                continue;
+            }
+            job.stage = JobStage::Failed;
+            job.attempts = 0;
+            job.parsed = None;
+            job.next_retry_at = Some(chrono::Utc::now().timestamp());
+            self.save(&job)?;
+            requeued += 1;
+        }
+        Ok(requeued)
+    }
+
+    /// Resolves every job awaiting review for `request` with an operator-confirmed
+    /// registered `vendor_name`, due date and/or invoice number, and schedules it for
+    /// immediate submission.
+    pub fn approve(
+        &self,
+        request: &Pubkey,
+        vendor_name: Option<&str>,
+        due_date: Option<i64>,
+        invoice_number: Option<&str>,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut approved = 0;
+        for entry in self.db.scan_prefix(format!("{}:", request)) {
+            let (_, bytes) = entry?;
+            let mut job: Job = serde_json::from_slice(&bytes)?;
+            if job.stage != JobStage::NeedsReview {
+                continue;
+            }
+            let Some(parsed) = job.parsed.as_mut() else {
+                continue;
+            };
+            if let Some(vendor_name) = vendor_name {
+                parsed.vendor = vendor_name.to_string();
+                parsed.vendor_score = 1.0;
+                parsed.vendor_ties.clear();
+                parsed.evidence.vendor = Some(Evidence::operator());
+            }
+            if let Some(due_date) = due_date {
+                parsed.due_date = due_date;
+                parsed.due_date_candidates.clear();
+                parsed.evidence.due_date = Some(Evidence::operator());
+            }
+            if let Some(invoice_number) = invoice_number {
+                parsed.invoice_number = invoice_number.to_string();
+                parsed.evidence.invoice_number = Some(Evidence::operator());
+            }
+            job.stage = JobStage::Failed;
+            job.attempts = 0;
+            job.next_retry_at = Some(chrono::Utc::now().timestamp());
+            self.save(&job)?;
+            approved += 1;
+        }
+        Ok(approved)
+    }
+
+    /// Schedules an on-chain rejection for every job of `request` that is awaiting review
+    /// or dead-lettered, for when an operator decides it cannot be processed.
+    pub fn schedule_rejection(&self, request: &Pubkey, rejection: Rejection) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut scheduled = 0;
+        for entry in self.db.scan_prefix(format!("{}:", request)) {
+            let (_, bytes) = entry?;
+            let mut job: Job = serde_json::from_slice(&bytes)?;
+            if !matches!(job.stage, JobStage::NeedsReview | JobStage::DeadLettered) {
+                continue;
+            }
+            job.rejection = Some(rejection);
+            job.last_error = Some("rejected by operator".to_string());
+            job.stage = JobStage::Failed;
+            job.attempts = 0;
+            job.next_retry_at = Some(chrono::Utc::now().timestamp());
+            self.save(&job)?;
+            scheduled += 1;
+        }
+        Ok(scheduled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evidence(rule: &str, confidence: f64) -> Option<Evidence> {
+        Some(Evidence { rule: rule.to_string(), line: Some(1), ocr_confidence: None, confidence })
+    }
+
+    #[test]
+    fn confidence_is_the_weakest_field() {
+        let evidence = FieldEvidence {
+            vendor: evidence("remit-to ~ registered vendor", 0.97),
+            amount: evidence("label 'amount due'", 0.95),
+            due_date: evidence("net-terms", 0.68),
+            invoice_number: evidence("invoice-number-label", 0.9),
+        };
+        assert_eq!(evidence.confidence(), 0.68);
+    }
+
+    #[test]
+    fn missing_fields_have_no_confidence() {
+        assert_eq!(FieldEvidence::default().confidence(), 0.0);
+
+        let evidence = FieldEvidence {
+            vendor: evidence("remit-to ~ registered vendor", 0.97),
+            amount: evidence("largest-currency-amount", 0.5),
+            due_date: evidence("net-terms", 0.68),
+            invoice_number: None,
+        };
+        assert_eq!(evidence.confidence(), 0.0);
+    }
+
+    #[test]
+    fn operator_values_are_certain() {
+        let evidence = FieldEvidence {
+            vendor: Some(Evidence::operator()),
+            amount: evidence("label 'total'", 0.8),
+            due_date: Some(Evidence::operator()),
+            invoice_number: Some(Evidence::operator()),
+        };
+        assert_eq!(evidence.confidence(), 0.8);
+    }
+
+    #[test]
+    fn invoices_without_evidence_submit_no_confidence() {
+        let parsed = ParsedInvoice {
+            vendor: "ACME Corp".to_string(),
+            vendor_candidates: vec!["ACME Corp.".to_string()],
+            vendor_score: 1.0,
+            vendor_ties: Vec::new(),
+            amount: 40_000_000,
+            due_date: 1_772_582_399,
+            due_date_candidates: Vec::new(),
+            invoice_number: "INV-0042".to_string(),
+            evidence: FieldEvidence::default(),
+        };
+        // Below any min_confidence_bps, so the program sends it to audit
+        assert_eq!(parsed.confidence_bps(), 0);
+
+        let parsed = ParsedInvoice {
+            evidence: FieldEvidence {
+                vendor: Some(Evidence::operator()),
+                amount: evidence("label 'total'", 1.0),
+                due_date: evidence("net-terms", 0.6543),
+                invoice_number: Some(Evidence::operator()),
+            },
+            ..parsed
+        };
+        assert_eq!(parsed.confidence_bps(), 6_543);
+    }
+}
@@ -0,0 +1,253 @@
+//! Events emitted on every invoice lifecycle transition and admin change.
+//!
+//! Status-changing events carry both the old and new status, so an indexer can
+//! rebuild an invoice's history from logs alone.
+
+use anchor_lang::prelude::*;
+use crate::state::*;
+
+#[event]
+pub struct InvoiceRequested {
+    pub org: Pubkey,
+    pub request: Pubkey,
+    pub invoice_id: u64,
+    pub authority: Pubkey,
+    pub ipfs_hash: String,
+    pub amount: u64,
+}
+
+#[event]
+pub struct InvoiceValidated {
+    pub org: Pubkey,
+    pub request: Pubkey,
+    pub invoice: Pubkey,
+    pub invoice_id: u64,
+    pub vendor: Pubkey,
+    pub amount: u64,
+    pub claimed_amount: u64,
+    pub confidence_bps: u16,
+    pub due_date: i64,
+    pub old_request_status: RequestStatus,
+    pub new_status: InvoiceStatus,
+}
+
+#[event]
+pub struct ExtractionRejected {
+    pub org: Pubkey,
+    pub request: Pubkey,
+    pub invoice_id: u64,
+    pub reason: RejectReason,
+    pub old_status: RequestStatus,
+    pub new_status: RequestStatus,
+}
+
+/// The extracted amount differs from the claimed one by more than the org's tolerance;
+/// the invoice skips the VRF draw and goes straight to audit
+#[event]
+pub struct AmountMismatch {
+    pub org: Pubkey,
+    pub invoice: Pubkey,
+    pub claimed_amount: u64,
+    pub extracted_amount: u64,
+    pub tolerance_bps: u16,
+}
+
+/// The oracle's confidence in the extraction is below the org's minimum; the invoice
+/// skips the VRF draw and goes straight to audit
+#[event]
+pub struct LowConfidence {
+    pub org: Pubkey,
+    pub invoice: Pubkey,
+    pub confidence_bps: u16,
+    pub min_confidence_bps: u16,
+}
+
+/// VRF outcome for a validated invoice, whether or not it was picked for audit
+#[event]
+pub struct AuditSelected {
+    pub org: Pubkey,
+    pub invoice: Pubkey,
+    pub selected: bool,
+    pub audit_rate_bps: u16,
+    pub old_status: InvoiceStatus,
+    pub new_status: InvoiceStatus,
+}
+
+#[event]
+pub struct AuditDecided {
+    pub org: Pubkey,
+    pub invoice: Pubkey,
+    pub reviewer: Pubkey,
+    pub approved: bool,
+    pub reason: AuditReason,
+    pub evidence_hash: String,
+    pub old_status: InvoiceStatus,
+    pub new_status: InvoiceStatus,
+    pub timestamp: i64,
+}
+
+/// Status-only payment path: the invoice is marked as paying without moving tokens
+#[event]
+pub struct PaymentProcessed {
+    pub org: Pubkey,
+    pub invoice: Pubkey,
+    pub amount: u64,
+    pub old_status: InvoiceStatus,
+    pub new_status: InvoiceStatus,
+}
+
+#[event]
+pub struct PaymentCompleted {
+    pub org: Pubkey,
+    pub invoice: Pubkey,
+    pub vendor: Pubkey,
+    pub amount: u64,
+    pub old_status: InvoiceStatus,
+    pub new_status: InvoiceStatus,
+}
+
+#[event]
+pub struct Escrowed {
+    pub org: Pubkey,
+    pub invoice: Pubkey,
+    pub payer: Pubkey,
+    pub escrow: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub old_status: InvoiceStatus,
+    pub new_status: InvoiceStatus,
+}
+
+#[event]
+pub struct Settled {
+    pub org: Pubkey,
+    pub invoice: Pubkey,
+    pub vendor: Pubkey,
+    pub vendor_token_account: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub old_status: InvoiceStatus,
+    pub new_status: InvoiceStatus,
+}
+
+#[event]
+pub struct InvoiceCancelled {
+    pub org: Pubkey,
+    pub invoice: Pubkey,
+    pub old_status: InvoiceStatus,
+    pub new_status: InvoiceStatus,
+}
+
+#[event]
+pub struct EscrowRefunded {
+    pub org: Pubkey,
+    pub invoice: Pubkey,
+    pub funder: Pubkey,
+    pub funder_token_account: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub old_status: InvoiceStatus,
+    pub new_status: InvoiceStatus,
+}
+
+/// `escrow_swept` is what was left in the escrow and returned to its funder before it was closed
+#[event]
+pub struct InvoiceClosed {
+    pub org: Pubkey,
+    pub invoice: Pubkey,
+    pub invoice_id: u64,
+    pub status: InvoiceStatus,
+    pub escrow_swept: u64,
+}
+
+#[event]
+pub struct RequestClosed {
+    pub org: Pubkey,
+    pub request: Pubkey,
+    pub invoice_id: u64,
+    pub status: RequestStatus,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum VendorChange {
+    Registered,
+    Deactivated,
+    Activated,
+    WalletUpdated,
+    Migrated,
+}
+
+/// An invoice moved from a legacy vendor address to the vendor's migrated account
+#[event]
+pub struct InvoiceVendorMigrated {
+    pub org: Pubkey,
+    pub invoice: Pubkey,
+    pub old_vendor: Pubkey,
+    pub new_vendor: Pubkey,
+}
+
+#[event]
+pub struct VendorChanged {
+    pub org: Pubkey,
+    pub vendor: Pubkey,
+    pub vendor_name: String,
+    pub change: VendorChange,
+    pub old_wallet: Pubkey,
+    pub new_wallet: Pubkey,
+    pub is_active: bool,
+}
+
+/// The admin-controlled part of `OrgConfig`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct OrgSettings {
+    pub oracle_signer: Pubkey,
+    pub auditors: Vec<Pubkey>,
+    pub treasury_vault: Pubkey,
+    pub mint: Pubkey,
+    pub per_invoice_cap: u64,
+    pub daily_cap: u64,
+    pub audit_rate_bps: u16,
+    pub amount_tolerance_bps: u16,
+    pub min_confidence_bps: u16,
+    pub paused: bool,
+}
+
+impl From<&OrgConfig> for OrgSettings {
+    fn from(cfg: &OrgConfig) -> Self {
+        Self {
+            oracle_signer: cfg.oracle_signer,
+            auditors: cfg.auditors.clone(),
+            treasury_vault: cfg.treasury_vault,
+            mint: cfg.mint,
+            per_invoice_cap: cfg.per_invoice_cap,
+            daily_cap: cfg.daily_cap,
+            audit_rate_bps: cfg.audit_rate_bps,
+            amount_tolerance_bps: cfg.amount_tolerance_bps,
+            min_confidence_bps: cfg.min_confidence_bps,
+            paused: cfg.paused,
+        }
+    }
+}
+
+#[event]
+pub struct OrgInitialized {
+    pub org: Pubkey,
+    pub authority: Pubkey,
+    pub settings: OrgSettings,
+}
+
+/// A pre-auditor org was grown to the current `OrgConfig` layout by `migrate_org_config`
+#[event]
+pub struct OrgConfigMigrated {
+    pub org: Pubkey,
+    pub authority: Pubkey,
+    pub settings: OrgSettings,
+}
+
+#[event]
+pub struct OrgConfigChanged {
+    pub org: Pubkey,
+    pub authority: Pubkey,
+    pub old: OrgSettings,
+    pub new: OrgSettings,
+}
@@ -0,0 +1,142 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use crate::events::*;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct CancelInvoice<'info> {
+    #[account(
+        mut,
+        seeds = [b"invoice", invoice_account.org.as_ref(), &invoice_account.invoice_id.to_le_bytes()],
+        bump,
+        has_one = authority @ InvoiceError::Unauthorized
+    )]
+    pub invoice_account: Account<'info, InvoiceAccount>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Cancels an unpaid invoice that holds no escrowed tokens; funded escrows go through `refund_escrow`.
+pub(crate) fn cancel_invoice(ctx: Context<CancelInvoice>) -> Result<()> {
+    let inv = &mut ctx.accounts.invoice_account;
+    require!(
+        !matches!(inv.status, InvoiceStatus::Paid | InvoiceStatus::Cancelled),
+        InvoiceError::InvalidStatus
+    );
+    // The status-only payment path reaches InEscrow without a funder or tokens
+    require!(
+        inv.escrow_funder == Pubkey::default(),
+        InvoiceError::EscrowActive
+    );
+
+    let old_status = inv.status;
+    inv.status = InvoiceStatus::Cancelled;
+
+    msg!("Invoice #{} cancelled", inv.invoice_id);
+    emit!(InvoiceCancelled {
+        org: inv.org,
+        invoice: inv.key(),
+        old_status,
+        new_status: inv.status,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RefundEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"org_config", org_config.authority.as_ref()],
+        bump = org_config.bump,
+        address = invoice_account.org @ InvoiceError::WrongOrg
+    )]
+    pub org_config: Account<'info, OrgConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"invoice", invoice_account.org.as_ref(), &invoice_account.invoice_id.to_le_bytes()],
+        bump,
+        has_one = authority @ InvoiceError::Unauthorized
+    )]
+    pub invoice_account: Account<'info, InvoiceAccount>,
+
+    /// CHECK: PDA only used as signing authority
+    #[account(
+        seeds = [b"escrow_auth", invoice_account.key().as_ref()],
+        bump
+    )]
+    pub escrow_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = escrow_authority
+    )]
+    pub escrow_ata: Account<'info, TokenAccount>,
+    /// Any token account of the mint owned by whoever funded the escrow
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = invoice_account.escrow_funder
+    )]
+    pub funder_ata: Account<'info, TokenAccount>,
+    #[account(address = org_config.mint @ InvoiceError::WrongMint)]
+    pub mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// The invoice owner must authorize the refund
+    pub authority: Signer<'info>,
+}
+
+/// Returns the whole escrow balance to the funder and cancels the invoice.
+/// Allowed while the org is paused, so funds are never trapped by a pause.
+/// A refund on the day the escrow was funded gives the amount back to the daily cap.
+pub(crate) fn refund_escrow(ctx: Context<RefundEscrow>) -> Result<()> {
+    let inv = &mut ctx.accounts.invoice_account;
+    require!(inv.status == InvoiceStatus::InEscrow, InvoiceError::InvalidStatus);
+    require!(inv.escrow_funder != Pubkey::default(), InvoiceError::InvalidStatus);
+
+    // Only invoice.amount was counted; stray transfers swept below never were
+    ctx.accounts
+        .org_config
+        .release_daily_spend(inv.amount, inv.funded_at, Clock::get()?.unix_timestamp);
+
+    // Sweep the balance rather than invoice.amount so stray transfers are returned too
+    let amount = ctx.accounts.escrow_ata.amount;
+
+    let bump = ctx.bumps.escrow_authority;
+    let invoice_key = inv.key();
+    let bump_seed = [bump];
+    let signer_seeds: &[&[u8]] = &[b"escrow_auth", invoice_key.as_ref(), &bump_seed];
+    let signer = &[signer_seeds];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.escrow_ata.to_account_info(),
+                to: ctx.accounts.funder_ata.to_account_info(),
+                authority: ctx.accounts.escrow_authority.to_account_info(),
+            },
+            signer,
+        ),
+        amount,
+    )?;
+
+    let old_status = inv.status;
+    inv.status = InvoiceStatus::Cancelled;
+
+    msg!("Refunded {} from escrow of invoice #{}", amount, inv.invoice_id);
+    emit!(EscrowRefunded {
+        org: inv.org,
+        invoice: invoice_key,
+        funder: inv.escrow_funder,
+        funder_token_account: ctx.accounts.funder_ata.key(),
+        mint: ctx.accounts.mint.key(),
+        amount,
+        old_status,
+        new_status: inv.status,
+    });
+    Ok(())
+}
@@ -1,4 +1,7 @@
 use anchor_lang::prelude::*;
+use anchor_spl::associated_token;
+use anchor_spl::token::{self, Token, TokenAccount};
+use crate::events::*;
 use crate::state::*;
 
 #[derive(Accounts)]
@@ -6,20 +9,101 @@ pub struct CloseInvoice<'info> {
     #[account(
         mut,
         close = authority,
-        seeds = [b"invoice", authority.key().as_ref()],
+        seeds = [b"invoice", invoice_account.org.as_ref(), &invoice_account.invoice_id.to_le_bytes()],
         bump,
         has_one = authority
     )]
     pub invoice_account: Account<'info, InvoiceAccount>,
 
+    // Source of the escrow mint
+    #[account(address = invoice_account.org @ InvoiceError::WrongOrg)]
+    pub org_config: Account<'info, OrgConfig>,
+
+    /// CHECK: PDA only used to derive and sign for the escrow token account
+    #[account(
+        seeds = [b"escrow_auth", invoice_account.key().as_ref()],
+        bump
+    )]
+    pub escrow_authority: UncheckedAccount<'info>,
+
+    /// CHECK: the invoice's escrow ATA, which may not exist; swept and closed if it does
+    #[account(
+        mut,
+        seeds = [escrow_authority.key().as_ref(), token::ID.as_ref(), org_config.mint.as_ref()],
+        seeds::program = associated_token::ID,
+        bump
+    )]
+    pub escrow_ata: UncheckedAccount<'info>,
+
+    /// Receives tokens sent to the escrow after it was paid out or refunded; only needed
+    /// when the escrow still holds a balance
+    #[account(
+        mut,
+        token::mint = org_config.mint,
+        token::authority = invoice_account.escrow_recipient()
+    )]
+    pub leftover_ata: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
     #[account(mut)]
     pub authority: Signer<'info>,
 }
 
-pub fn close_invoice(ctx: Context<CloseInvoice>) -> Result<()> {
+/// Closes a finished invoice. Anything left in its escrow goes back to the funder and the
+/// escrow token account is closed, so stray transfers cannot keep the invoice open.
+pub(crate) fn close_invoice(ctx: Context<CloseInvoice>) -> Result<()> {
     let invoice = &ctx.accounts.invoice_account;
+    require!(invoice.status != InvoiceStatus::InEscrow, InvoiceError::EscrowActive);
+
+    let mut escrow_swept = 0;
+    let escrow = &ctx.accounts.escrow_ata;
+    if escrow.owner == &token::ID && !escrow.data_is_empty() {
+        escrow_swept = TokenAccount::try_deserialize(&mut &escrow.try_borrow_data()?[..])?.amount;
+
+        let bump = ctx.bumps.escrow_authority;
+        let invoice_key = invoice.key();
+        let bump_seed = [bump];
+        let signer_seeds: &[&[u8]] = &[b"escrow_auth", invoice_key.as_ref(), &bump_seed];
+        let signer = &[signer_seeds];
+
+        if escrow_swept > 0 {
+            let leftover_ata = ctx.accounts.leftover_ata.as_ref().ok_or(InvoiceError::EscrowNotEmpty)?;
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: escrow.to_account_info(),
+                        to: leftover_ata.to_account_info(),
+                        authority: ctx.accounts.escrow_authority.to_account_info(),
+                    },
+                    signer,
+                ),
+                escrow_swept,
+            )?;
+            msg!("Returned {} left in escrow to {}", escrow_swept, leftover_ata.key());
+        }
+
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::CloseAccount {
+                account: escrow.to_account_info(),
+                destination: ctx.accounts.authority.to_account_info(),
+                authority: ctx.accounts.escrow_authority.to_account_info(),
+            },
+            signer,
+        ))?;
+    }
+
     msg!("Closing invoice account for vendor: {}", invoice.vendor_name);
     msg!("Rent returned to: {}", ctx.accounts.authority.key());
+    emit!(InvoiceClosed {
+        org: invoice.org,
+        invoice: invoice.key(),
+        invoice_id: invoice.invoice_id,
+        status: invoice.status,
+        escrow_swept,
+    });
     Ok(())
 }
 
@@ -28,7 +112,7 @@ pub struct CloseRequest<'info> {
     #[account(
         mut,
         close = authority,
-        seeds = [b"request", authority.key().as_ref()],
+        seeds = [b"request", invoice_request.org.as_ref(), &invoice_request.invoice_id.to_le_bytes()],
         bump,
         has_one = authority
     )]
@@ -39,9 +123,15 @@ pub struct CloseRequest<'info> {
 }
 
 
-pub fn close_request(ctx: Context<CloseRequest>) -> Result<()> {
+pub(crate) fn close_request(ctx: Context<CloseRequest>) -> Result<()> {
     let request = &ctx.accounts.invoice_request;
     msg!("Closing request account for IPFS: {}", request.ipfs_hash);
     msg!("Rent returned to: {}", ctx.accounts.authority.key());
+    emit!(RequestClosed {
+        org: request.org,
+        request: request.key(),
+        invoice_id: request.invoice_id,
+        status: request.status,
+    });
     Ok(())
 }
@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token};
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use crate::events::*;
 use crate::state::*;
 
 #[derive(Accounts)]
@@ -7,13 +8,14 @@ pub struct FundEscrow<'info> {
     #[account(
         mut,
         seeds = [b"org_config", org_config.authority.as_ref()],
-        bump = org_config.bump
+        bump = org_config.bump,
+        address = invoice_account.org @ InvoiceError::WrongOrg
     )]
     pub org_config: Account<'info, OrgConfig>,
 
     #[account(
         mut,
-        seeds = [b"invoice", authority.key().as_ref()],
+        seeds = [b"invoice", invoice_account.org.as_ref(), &invoice_account.invoice_id.to_le_bytes()],
         bump,
         has_one = authority @ InvoiceError::Unauthorized
     )]
@@ -31,20 +33,27 @@ pub struct FundEscrow<'info> {
     /// CHECK: must equal invoice_account.authority (validated by constraint above)
     pub authority: UncheckedAccount<'info>,
 
-    #[account(mut)]
-    /// CHECK: payer's SPL token account (validated at runtime)
-    pub payer_ata: UncheckedAccount<'info>,
-    #[account(mut)]
-    /// CHECK: escrow SPL token account owned by escrow authority PDA
-    pub escrow_ata: UncheckedAccount<'info>,
-    /// CHECK: SPL token mint; key checked against OrgConfig
-    pub mint: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = payer
+    )]
+    pub payer_ata: Account<'info, TokenAccount>,
+    /// Escrow ATA of the escrow authority PDA; create it before funding
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = escrow_authority
+    )]
+    pub escrow_ata: Account<'info, TokenAccount>,
+    #[account(address = org_config.mint @ InvoiceError::WrongMint)]
+    pub mint: Account<'info, Mint>,
 
     pub token_program: Program<'info, Token>,
 }
 
-pub fn fund_escrow(ctx: Context<FundEscrow>) -> Result<()> {
-    let cfg = &ctx.accounts.org_config;
+pub(crate) fn fund_escrow(ctx: Context<FundEscrow>) -> Result<()> {
+    let cfg = &mut ctx.accounts.org_config;
     require!(!cfg.paused, InvoiceError::OrgPaused);
 
     let inv = &mut ctx.accounts.invoice_account;
@@ -55,11 +64,12 @@ pub fn fund_escrow(ctx: Context<FundEscrow>) -> Result<()> {
     );
     require!(amount <= cfg.per_invoice_cap, InvoiceError::CapExceeded);
 
-    // Ensure mint matches configuration
-    require_keys_eq!(ctx.accounts.mint.key(), cfg.mint, InvoiceError::WrongMint);
+    // Funds are counted against the daily cap when they leave the treasury; settling
+    // the escrow later moves already-counted tokens
+    let now = Clock::get()?.unix_timestamp;
+    cfg.record_daily_spend(amount, now)?;
 
     // Transfer tokens from payer to escrow
-       // Transfer tokens from payer to escrow
     token::transfer( CpiContext::new(
         ctx.accounts.token_program.to_account_info(),
      anchor_spl::token::Transfer {
@@ -69,20 +79,35 @@ pub fn fund_escrow(ctx: Context<FundEscrow>) -> Result<()> {
     }),
     amount)?;
 
+    let old_status = inv.status;
     inv.status = InvoiceStatus::InEscrow;
+    inv.escrow_funder = ctx.accounts.payer.key();
+    inv.funded_at = now;
+    emit!(Escrowed {
+        org: cfg.key(),
+        invoice: inv.key(),
+        payer: ctx.accounts.payer.key(),
+        escrow: ctx.accounts.escrow_ata.key(),
+        mint: cfg.mint,
+        amount,
+        old_status,
+        new_status: inv.status,
+    });
     Ok(())
 }
 
 #[derive(Accounts)]
 pub struct SettleToVendor<'info> {
     #[account(
-        mut
+        seeds = [b"org_config", org_config.authority.as_ref()],
+        bump = org_config.bump,
+        address = invoice_account.org @ InvoiceError::WrongOrg
     )]
     pub org_config: Account<'info, OrgConfig>,
 
     #[account(
         mut,
-        seeds = [b"invoice", authority.key().as_ref()],
+        seeds = [b"invoice", invoice_account.org.as_ref(), &invoice_account.invoice_id.to_le_bytes()],
         bump,
         has_one = authority @ InvoiceError::Unauthorized
     )]
@@ -95,14 +120,25 @@ pub struct SettleToVendor<'info> {
     )]
     pub escrow_authority: UncheckedAccount<'info>,
 
-    #[account(mut)]
-    /// CHECK: vendor's SPL token account (validated at runtime)
-    pub vendor_ata: UncheckedAccount<'info>,
-    #[account(mut)]
-    /// CHECK: escrow SPL token account owned by escrow authority PDA
-    pub escrow_ata: UncheckedAccount<'info>,
-    /// CHECK: SPL token mint
-    pub mint: UncheckedAccount<'info>,
+    /// The vendor the invoice was matched to at extraction
+    #[account(mut, address = invoice_account.vendor @ InvoiceError::InvalidVendor)]
+    pub vendor_account: Account<'info, VendorAccount>,
+
+    /// Any token account of the mint owned by the vendor's registered wallet
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = vendor_account.wallet
+    )]
+    pub vendor_ata: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = escrow_authority
+    )]
+    pub escrow_ata: Account<'info, TokenAccount>,
+    #[account(address = org_config.mint @ InvoiceError::WrongMint)]
+    pub mint: Account<'info, Mint>,
 
     pub token_program: Program<'info, Token>,
 
@@ -110,10 +146,14 @@ pub struct SettleToVendor<'info> {
     pub authority: Signer<'info>,
 }
 
-pub fn settle_to_vendor(ctx: Context<SettleToVendor>) -> Result<()> {
+pub(crate) fn settle_to_vendor(ctx: Context<SettleToVendor>) -> Result<()> {
     let cfg = &ctx.accounts.org_config;
     require!(!cfg.paused, InvoiceError::OrgPaused);
 
+    let vendor = &ctx.accounts.vendor_account;
+    require!(vendor.is_active, InvoiceError::VendorInactive);
+    require_keys_eq!(vendor.org, cfg.key(), InvoiceError::WrongOrg);
+
     let inv = &mut ctx.accounts.invoice_account;
     require!(inv.status == InvoiceStatus::InEscrow, InvoiceError::InvalidStatus);
     let amount = inv.amount;
@@ -133,6 +173,21 @@ pub fn settle_to_vendor(ctx: Context<SettleToVendor>) -> Result<()> {
         authority: ctx.accounts.escrow_authority.to_account_info(),
     },signer,),amount)?;
 
+    let old_status = inv.status;
     inv.status = InvoiceStatus::Paid;
+
+    ctx.accounts
+        .vendor_account
+        .record_payment(amount, Clock::get()?.unix_timestamp)?;
+    emit!(Settled {
+        org: ctx.accounts.org_config.key(),
+        invoice: inv.key(),
+        vendor: ctx.accounts.vendor_account.key(),
+        vendor_token_account: ctx.accounts.vendor_ata.key(),
+        mint: ctx.accounts.mint.key(),
+        amount,
+        old_status,
+        new_status: inv.status,
+    });
     Ok(())
 }
@@ -1,14 +1,23 @@
 use anchor_lang::prelude::*;
+use crate::events::*;
 use crate::state::*;
 
 #[derive(Accounts)]
 #[instruction(ipfs_hash: String)]
 pub struct RequestExtraction<'info> {
+    // Source of the invoice_id sequence
+    #[account(
+        mut,
+        seeds = [b"org_config", org_config.authority.as_ref()],
+        bump = org_config.bump
+    )]
+    pub org_config: Account<'info, OrgConfig>,
+
     #[account(
         init,
         payer = authority,
         space = 8 + InvoiceRequest::INIT_SPACE,
-        seeds = [b"request", authority.key().as_ref()],
+        seeds = [b"request", org_config.key().as_ref(), &org_config.invoice_counter.to_le_bytes()],
         bump
     )]
     pub invoice_request: Account<'info, InvoiceRequest>,
@@ -19,7 +28,7 @@ pub struct RequestExtraction<'info> {
     pub system_program: Program<'info, System>,
 }
 
-pub fn request_invoice_extraction(
+pub(crate) fn request_invoice_extraction(
     ctx: Context<RequestExtraction>,
     ipfs_hash: String,
     amount: u64
@@ -27,20 +36,35 @@ pub fn request_invoice_extraction(
     require!(!ipfs_hash.is_empty(), InvoiceError::InvalidIPFSHash);
     require!(amount > 0, InvoiceError::InvalidAmount);
 
+    let org_config = &mut ctx.accounts.org_config;
+    let invoice_id = org_config.invoice_counter;
+    org_config.invoice_counter = invoice_id.checked_add(1).ok_or(InvoiceError::Overflow)?;
+
     ctx.accounts.invoice_request.set_inner(InvoiceRequest{
         authority: ctx.accounts.authority.key(),
+        org: org_config.key(),
+        invoice_id,
         ipfs_hash: ipfs_hash.clone(),
         status: RequestStatus::Pending,
         timestamp: Clock::get()?.unix_timestamp,
-        amount
+        amount,
+        reject_reason: None,
     });
 
-    msg!("Invoice extraction requested for IPFS: {}", ipfs_hash);
+    msg!("Invoice #{} extraction requested for IPFS: {}", invoice_id, ipfs_hash);
+    emit!(InvoiceRequested {
+        org: org_config.key(),
+        request: ctx.accounts.invoice_request.key(),
+        invoice_id,
+        authority: ctx.accounts.authority.key(),
+        ipfs_hash,
+        amount,
+    });
     Ok(())
 }
 
 #[derive(Accounts)]
-#[instruction(vendor_name: String)]  //needed for vendor PDA derivation
+#[instruction(vendor_name: String, amount: u64, due_date: i64, invoice_number: String)]  //needed for vendor and registry PDA derivation
 pub struct ProcessResult<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
@@ -54,14 +78,15 @@ pub struct ProcessResult<'info> {
 
     // VendorAccount to validate vendor is registered and active
     #[account(
-        seeds = [b"vendor", org_config.key().as_ref(), vendor_name.as_bytes()],
+        seeds = [b"vendor", org_config.key().as_ref(), &vendor_seed(&vendor_name)],
         bump
     )]
     pub vendor_account: Account<'info, VendorAccount>,
 
+    // Seeded by this org_config, so a request can only be fulfilled under its own org
     #[account(
         mut,
-        seeds = [b"request", invoice_request.authority.as_ref()],
+        seeds = [b"request", org_config.key().as_ref(), &invoice_request.invoice_id.to_le_bytes()],
         bump
     )]
     pub invoice_request: Account<'info, InvoiceRequest>,
@@ -70,19 +95,40 @@ pub struct ProcessResult<'info> {
         init,
         payer = payer,
         space = 8 + InvoiceAccount::INIT_SPACE,
-        seeds = [b"invoice", invoice_request.authority.as_ref()],
+        seeds = [b"invoice", org_config.key().as_ref(), &invoice_request.invoice_id.to_le_bytes()],
         bump
     )]
     pub invoice_account: Account<'info, InvoiceAccount>,
 
+    // Duplicate guards; an existing, claimed registry means the invoice was seen before
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + InvoiceRegistry::INIT_SPACE,
+        seeds = [b"doc_hash", org_config.key().as_ref(), &document_hash(&invoice_request.ipfs_hash)],
+        bump
+    )]
+    pub document_registry: Account<'info, InvoiceRegistry>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + InvoiceRegistry::INIT_SPACE,
+        seeds = [b"fingerprint", org_config.key().as_ref(), &invoice_fingerprint(&vendor_account.key(), &invoice_number, amount)],
+        bump
+    )]
+    pub fingerprint_registry: Account<'info, InvoiceRegistry>,
+
     pub system_program: Program<'info, System>,
 }
 
-pub fn process_extraction_result(
+pub(crate) fn process_extraction_result(
     ctx: Context<ProcessResult>,
     vendor_name: String,
     amount: u64,
     due_date: i64,
+    invoice_number: String,
+    confidence_bps: u16,
 ) -> Result<()> {
 
     let org_config = &mut ctx.accounts.org_config;
@@ -93,16 +139,37 @@ pub fn process_extraction_result(
         org_config.oracle_signer,
         InvoiceError::Unauthorized
     );
+    // A rejected request stays rejected; the oracle cannot revive it with a late result
+    require!(
+        ctx.accounts.invoice_request.status == RequestStatus::Pending,
+        InvoiceError::InvalidStatus
+    );
 
     // Validate extracted data
     require!(amount > 0, InvoiceError::InvalidAmount);
     require!(amount <= org_config.per_invoice_cap, InvoiceError::CapExceeded);
     require!(!vendor_name.is_empty(), InvoiceError::InvalidVendor);
     require!(vendor_name.len() <= 50, InvoiceError::InvalidVendor);
+    require!(invoice_number.len() <= 32, InvoiceError::InvalidInvoiceNumber);
+    require!(confidence_bps <= 10_000, InvoiceError::InvalidConfidence);
+    require!(
+        !normalize_invoice_number(&invoice_number).is_empty(),
+        InvoiceError::InvalidInvoiceNumber
+    );
 
     let current_time = Clock::get()?.unix_timestamp;
     require!(due_date > current_time, InvoiceError::InvalidDueDate);
 
+    let invoice_key = ctx.accounts.invoice_account.key();
+    for registry in [&mut ctx.accounts.document_registry, &mut ctx.accounts.fingerprint_registry] {
+        require_keys_eq!(registry.invoice, Pubkey::default(), InvoiceError::DuplicateInvoice);
+        registry.set_inner(InvoiceRegistry {
+            org: org_config.key(),
+            invoice: invoice_key,
+            created_at: current_time,
+        });
+    }
+
     // Verify vendor is registered and active (CRITICAL for whitelist)
     let vendor = &ctx.accounts.vendor_account;
     require!(vendor.is_active, InvoiceError::VendorInactive);
@@ -110,23 +177,122 @@ pub fn process_extraction_result(
     let invoice = &mut ctx.accounts.invoice_account;
     let request = &mut ctx.accounts.invoice_request;
 
+    // A document that disagrees with what the submitter claimed, or that the oracle could
+    // not read confidently, always gets a human look whatever the VRF would draw
+    let amount_matches = org_config.amount_within_tolerance(request.amount, amount);
+    let confident = confidence_bps >= org_config.min_confidence_bps;
+    let status = if amount_matches && confident {
+        InvoiceStatus::Validated
+    } else {
+        InvoiceStatus::AuditPending
+    };
+
     invoice.set_inner(InvoiceAccount{
         authority: request.authority,
+        org: request.org,
+        invoice_id: request.invoice_id,
         vendor_name,
+        invoice_number,
         amount,
+        claimed_amount: request.amount,
+        confidence_bps,
         due_date,
         ipfs_hash: request.ipfs_hash.clone(),
-        status: InvoiceStatus::Validated,
+        status,
         timestamp: Clock::get()?.unix_timestamp,
         vendor: ctx.accounts.vendor_account.key(),
+        escrow_funder: Pubkey::default(),
+        funded_at: 0,
+        reviewer: Pubkey::default(),
+        reviewed_at: 0,
     });
 
+    let old_request_status = request.status;
     request.status = RequestStatus::Completed;
-    msg!("Invoice processed: {} - ${}", invoice.vendor_name, invoice.amount);
+    msg!("Invoice #{} processed: {} - ${}", invoice.invoice_id, invoice.vendor_name, invoice.amount);
+    emit!(InvoiceValidated {
+        org: invoice.org,
+        request: request.key(),
+        invoice: invoice.key(),
+        invoice_id: invoice.invoice_id,
+        vendor: invoice.vendor,
+        amount,
+        claimed_amount: invoice.claimed_amount,
+        confidence_bps,
+        due_date,
+        old_request_status,
+        new_status: invoice.status,
+    });
+    if !amount_matches {
+        msg!("Amount mismatch: claimed {}, extracted {} - sent to audit", invoice.claimed_amount, amount);
+        emit!(AmountMismatch {
+            org: invoice.org,
+            invoice: invoice.key(),
+            claimed_amount: invoice.claimed_amount,
+            extracted_amount: amount,
+            tolerance_bps: org_config.amount_tolerance_bps,
+        });
+    }
+    if !confident {
+        msg!("Low extraction confidence: {} bps - sent to audit", confidence_bps);
+        emit!(LowConfidence {
+            org: invoice.org,
+            invoice: invoice.key(),
+            confidence_bps,
+            min_confidence_bps: org_config.min_confidence_bps,
+        });
+    }
     Ok(())
 }
+#[derive(Accounts)]
+pub struct RejectExtraction<'info> {
+    pub oracle: Signer<'info>,
+
+    #[account(
+        seeds = [b"org_config", org_config.authority.as_ref()],
+        bump = org_config.bump
+    )]
+    pub org_config: Account<'info, OrgConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"request", org_config.key().as_ref(), &invoice_request.invoice_id.to_le_bytes()],
+        bump
+    )]
+    pub invoice_request: Account<'info, InvoiceRequest>,
+}
+
+/// Marks a request the oracle cannot process as Failed, so the submitter sees why
+/// instead of it staying Pending forever.
+pub(crate) fn reject_extraction(ctx: Context<RejectExtraction>, reason: RejectReason) -> Result<()> {
+    require_keys_eq!(
+        ctx.accounts.oracle.key(),
+        ctx.accounts.org_config.oracle_signer,
+        InvoiceError::Unauthorized
+    );
+
+    let request = &mut ctx.accounts.invoice_request;
+    require!(request.status == RequestStatus::Pending, InvoiceError::InvalidStatus);
+
+    let old_status = request.status;
+    request.status = RequestStatus::Failed;
+    request.reject_reason = Some(reason);
+
+    msg!("Invoice #{} extraction rejected", request.invoice_id);
+    emit!(ExtractionRejected {
+        org: request.org,
+        request: request.key(),
+        invoice_id: request.invoice_id,
+        reason,
+        old_status,
+        new_status: request.status,
+    });
+    Ok(())
+}
+
 // Manual review decision after VRF selects the invoice for audit
 #[derive(Accounts)]
+#[instruction(approve: bool, reason: AuditReason, evidence_hash: String)]
 pub struct AuditDecide<'info> {
     #[account(mut)]
     pub reviewer: Signer<'info>,
@@ -134,34 +300,80 @@ pub struct AuditDecide<'info> {
     #[account(
         mut,
         seeds = [b"org_config", org_config.authority.as_ref()],
-        bump
+        bump,
+        address = invoice_account.org @ InvoiceError::WrongOrg
     )]
     pub org_config: Account<'info, OrgConfig>,
 
     #[account(
         mut,
-        seeds = [b"invoice", invoice_account.authority.as_ref()],
+        seeds = [b"invoice", invoice_account.org.as_ref(), &invoice_account.invoice_id.to_le_bytes()],
         bump
     )]
     pub invoice_account: Account<'info, InvoiceAccount>,
+
+    // Outlives the invoice: close_invoice leaves it in place for compliance
+    #[account(
+        init,
+        payer = reviewer,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", invoice_account.key().as_ref()],
+        bump
+    )]
+    pub audit_record: Account<'info, AuditRecord>,
+
+    pub system_program: Program<'info, System>,
 }
 
-pub fn audit_decide(ctx: Context<AuditDecide>, approve: bool) -> Result<()> {
+pub(crate) fn audit_decide(
+    ctx: Context<AuditDecide>,
+    approve: bool,
+    reason: AuditReason,
+    evidence_hash: String,
+) -> Result<()> {
+    require!(!evidence_hash.is_empty(), InvoiceError::InvalidEvidence);
+    require!(evidence_hash.len() <= 64, InvoiceError::InvalidEvidence);
+
     let org = &ctx.accounts.org_config;
-    // Only org authority or oracle signer can decide
-    require!(
-        ctx.accounts.reviewer.key() == org.authority || ctx.accounts.reviewer.key() == org.oracle_signer,
-        InvoiceError::Unauthorized
-    );
+    let reviewer = ctx.accounts.reviewer.key();
+    // Only a registered auditor can decide, and never the oracle that extracted the invoice
+    require_keys_neq!(reviewer, org.oracle_signer, InvoiceError::OracleCannotAudit);
+    require!(org.is_auditor(&reviewer), InvoiceError::NotAuditor);
 
     let invoice = &mut ctx.accounts.invoice_account;
     require!(invoice.status == InvoiceStatus::AuditPending, InvoiceError::InvalidStatus);
 
+    let old_status = invoice.status;
     invoice.status = if approve {
         InvoiceStatus::ReadyForPayment
     } else {
         InvoiceStatus::Rejected
     };
+    invoice.reviewer = reviewer;
+    invoice.reviewed_at = Clock::get()?.unix_timestamp;
+
+    ctx.accounts.audit_record.set_inner(AuditRecord {
+        org: org.key(),
+        invoice: invoice.key(),
+        reviewer,
+        approved: approve,
+        reason,
+        evidence_hash: evidence_hash.clone(),
+        decided_at: invoice.reviewed_at,
+        bump: ctx.bumps.audit_record,
+    });
+
+    emit!(AuditDecided {
+        org: org.key(),
+        invoice: invoice.key(),
+        reviewer,
+        approved: approve,
+        reason,
+        evidence_hash,
+        old_status,
+        new_status: invoice.status,
+        timestamp: invoice.reviewed_at,
+    });
 
     Ok(())
 }
@@ -3,6 +3,7 @@ pub mod invoice;
 pub mod payments;
 pub mod escrow;
 pub mod close;
+pub mod cancel;
 pub mod vendor;
 pub mod vrf;
 
@@ -11,5 +12,6 @@ pub use invoice::*;
 pub use payments::*;
 pub use escrow::*;
 pub use close::*;
+pub use cancel::*;
 pub use vendor::*;
 pub use vrf::*;
@@ -1,4 +1,6 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use crate::events::*;
 use crate::state::*;
 
 #[derive(Accounts)]
@@ -17,7 +19,7 @@ pub struct OrgInit<'info> {
     pub system_program: Program<'info, System>,
 }
 
-pub fn org_init(
+pub(crate) fn org_init(
     ctx: Context<OrgInit>,
     treasury_vault: Pubkey,
     mint: Pubkey,
@@ -34,6 +36,7 @@ pub fn org_init(
     cfg.set_inner(OrgConfig{
         authority: ctx.accounts.authority.key(),
         oracle_signer: ctx.accounts.authority.key(),
+        auditors: Vec::new(),
         treasury_vault,
         mint,
         per_invoice_cap,
@@ -41,16 +44,87 @@ pub fn org_init(
         daily_spent: 0,
         last_reset_day: Clock::get()?.unix_timestamp / 86400,
         audit_rate_bps,
+        // Exact match until the authority widens it with update_org_config
+        amount_tolerance_bps: 0,
+        min_confidence_bps: 0,
         paused: false,
         invoice_counter: 0,
-        version: 1,
+        version: ORG_CONFIG_VERSION,
         bump: ctx.bumps.org_config
     });
 
     msg!("Organization initialized - authority: {}", cfg.authority);
+    emit!(OrgInitialized {
+        org: cfg.key(),
+        authority: cfg.authority,
+        settings: OrgSettings::from(&**cfg),
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct MigrateOrgConfig<'info> {
+    /// CHECK: still in the pre-auditor layout, so it cannot load as an OrgConfig; read as
+    /// a LegacyOrgConfig, then grown and rewritten in place
+    #[account(
+        mut,
+        seeds = [b"org_config", authority.key().as_ref()],
+        bump
+    )]
+    pub org_config: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Grows an org created before auditors, the amount tolerance and the confidence threshold
+/// to the current layout. Every other instruction fails to load the org until this runs.
+pub(crate) fn migrate_org_config(ctx: Context<MigrateOrgConfig>) -> Result<()> {
+    let info = ctx.accounts.org_config.to_account_info();
+    let legacy = LegacyOrgConfig::try_from_account(&info)?;
+    require_keys_eq!(legacy.authority, ctx.accounts.authority.key(), InvoiceError::Unauthorized);
+    let cfg = legacy.into_org_config();
+
+    // The authority pays the rent for the extra space
+    let space = 8 + OrgConfig::INIT_SPACE;
+    let shortfall = Rent::get()?.minimum_balance(space).saturating_sub(info.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    info.resize(space)?;
+    cfg.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+    msg!("Organization migrated - authority: {}", cfg.authority);
+    emit!(OrgConfigMigrated {
+        org: info.key(),
+        authority: cfg.authority,
+        settings: OrgSettings::from(&cfg),
+    });
     Ok(())
 }
 
+#[derive(Accounts)]
+pub struct DailyHeadroom<'info> {
+    pub org_config: Account<'info, OrgConfig>,
+}
+
+/// Read-only: how much can still be escrowed or paid today. Call via simulation.
+pub(crate) fn get_daily_headroom(ctx: Context<DailyHeadroom>) -> Result<u64> {
+    let headroom = ctx.accounts.org_config.daily_headroom(Clock::get()?.unix_timestamp);
+    msg!("Daily headroom: {}", headroom);
+    Ok(headroom)
+}
+
 #[derive(Accounts)]
 pub struct UpdateOrgConfig<'info> {
     pub authority: Signer<'info>,
@@ -62,11 +136,12 @@ pub struct UpdateOrgConfig<'info> {
     
 }
 
-pub fn update_org_config(
+pub(crate) fn update_org_config(
     ctx: Context<UpdateOrgConfig>,
     args: UpdateOrgConfigArgs,
 ) -> Result<()> {
     let cfg = &mut ctx.accounts.org_config;
+    let old = OrgSettings::from(&**cfg);
 
     if let (Some(per_invoice_cap), Some(daily_cap)) = (args.per_invoice_cap, args.daily_cap) {
         require!(per_invoice_cap > 0, InvoiceError::InvalidAmount);
@@ -82,10 +157,66 @@ pub fn update_org_config(
         msg!("Pause state updated: {}", paused);
     }
 
+    if let Some(amount_tolerance_bps) = args.amount_tolerance_bps {
+        require!(amount_tolerance_bps <= 10_000, InvoiceError::InvalidTolerance);
+        cfg.amount_tolerance_bps = amount_tolerance_bps;
+        msg!("Amount tolerance updated: {} bps", amount_tolerance_bps);
+    }
+
+    if let Some(min_confidence_bps) = args.min_confidence_bps {
+        require!(min_confidence_bps <= 10_000, InvoiceError::InvalidConfidence);
+        cfg.min_confidence_bps = min_confidence_bps;
+        msg!("Minimum extraction confidence updated: {} bps", min_confidence_bps);
+    }
+
     if let Some(oracle_signer) = args.oracle_signer {
+        require!(!cfg.is_auditor(&oracle_signer), InvoiceError::OracleCannotAudit);
         cfg.oracle_signer = oracle_signer;
         msg!("Oracle signer updated to: {}", oracle_signer);
     }
 
+    emit!(OrgConfigChanged {
+        org: cfg.key(),
+        authority: cfg.authority,
+        old,
+        new: OrgSettings::from(&**cfg),
+    });
+    Ok(())
+}
+
+/// Registers an auditor who may decide invoices flagged for audit.
+pub(crate) fn add_auditor(ctx: Context<UpdateOrgConfig>, auditor: Pubkey) -> Result<()> {
+    let cfg = &mut ctx.accounts.org_config;
+    require_keys_neq!(auditor, cfg.oracle_signer, InvoiceError::OracleCannotAudit);
+    require!(!cfg.is_auditor(&auditor), InvoiceError::DuplicateAuditor);
+    require!(cfg.auditors.len() < MAX_AUDITORS, InvoiceError::TooManyAuditors);
+
+    let old = OrgSettings::from(&**cfg);
+    cfg.auditors.push(auditor);
+    msg!("Auditor added: {}", auditor);
+
+    emit!(OrgConfigChanged {
+        org: cfg.key(),
+        authority: cfg.authority,
+        old,
+        new: OrgSettings::from(&**cfg),
+    });
+    Ok(())
+}
+
+pub(crate) fn remove_auditor(ctx: Context<UpdateOrgConfig>, auditor: Pubkey) -> Result<()> {
+    let cfg = &mut ctx.accounts.org_config;
+    require!(cfg.is_auditor(&auditor), InvoiceError::NotAuditor);
+
+    let old = OrgSettings::from(&**cfg);
+    cfg.auditors.retain(|a| a != &auditor);
+    msg!("Auditor removed: {}", auditor);
+
+    emit!(OrgConfigChanged {
+        org: cfg.key(),
+        authority: cfg.authority,
+        old,
+        new: OrgSettings::from(&**cfg),
+    });
     Ok(())
 }
@@ -1,20 +1,25 @@
 use anchor_lang::prelude::*;
+use crate::events::*;
 use crate::state::*;
 
 #[derive(Accounts)]
 pub struct ProcessPayment<'info> {
     #[account(
         mut,
-        seeds = [b"invoice", authority.key().as_ref()],
+        seeds = [b"invoice", invoice_account.org.as_ref(), &invoice_account.invoice_id.to_le_bytes()],
         bump,
         has_one = authority
     )]
     pub invoice_account: Account<'info, InvoiceAccount>,
 
+    // Daily cap accounting for the status-only payment path
+    #[account(mut, address = invoice_account.org @ InvoiceError::WrongOrg)]
+    pub org_config: Account<'info, OrgConfig>,
+
     pub authority: Signer<'info>,
 }
 
-pub fn process_invoice_payment(ctx: Context<ProcessPayment>) -> Result<()> {
+pub(crate) fn process_invoice_payment(ctx: Context<ProcessPayment>) -> Result<()> {
     let invoice = &ctx.accounts.invoice_account;
     require!(
         invoice.status == InvoiceStatus::ReadyForPayment,
@@ -24,14 +29,24 @@ pub fn process_invoice_payment(ctx: Context<ProcessPayment>) -> Result<()> {
     let current_time = Clock::get()?.unix_timestamp;
     require!(current_time <= invoice.due_date, InvoiceError::PaymentOverdue);
 
+    ctx.accounts.org_config.record_daily_spend(invoice.amount, current_time)?;
+
     msg!("Processing payment for invoice:");
     msg!("Vendor: {}", invoice.vendor_name);
     msg!("Amount: {}", invoice.amount);
     msg!("Due Date: {}", invoice.due_date);
 
     let invoice_mut = &mut ctx.accounts.invoice_account;
+    let old_status = invoice_mut.status;
     invoice_mut.status = InvoiceStatus::InEscrow;
     msg!("Invoice moved to escrow");
+    emit!(PaymentProcessed {
+        org: invoice_mut.org,
+        invoice: invoice_mut.key(),
+        amount: invoice_mut.amount,
+        old_status,
+        new_status: invoice_mut.status,
+    });
     Ok(())
 }
 
@@ -39,19 +54,35 @@ pub fn process_invoice_payment(ctx: Context<ProcessPayment>) -> Result<()> {
 pub struct CompletePayment<'info> {
     #[account(
         mut,
-        seeds = [b"invoice", authority.key().as_ref()],
+        seeds = [b"invoice", invoice_account.org.as_ref(), &invoice_account.invoice_id.to_le_bytes()],
         bump,
         has_one = authority
     )]
     pub invoice_account: Account<'info, InvoiceAccount>,
 
+    #[account(mut, address = invoice_account.vendor @ InvoiceError::InvalidVendor)]
+    pub vendor_account: Account<'info, VendorAccount>,
+
     pub authority: Signer<'info>,
 }
 
-pub fn complete_payment(ctx: Context<CompletePayment>) -> Result<()> {
+pub(crate) fn complete_payment(ctx: Context<CompletePayment>) -> Result<()> {
     let invoice = &mut ctx.accounts.invoice_account;
     require!(invoice.status == InvoiceStatus::InEscrow, InvoiceError::InvalidStatus);
+    let old_status = invoice.status;
     invoice.status = InvoiceStatus::Paid;
+
+    ctx.accounts
+        .vendor_account
+        .record_payment(invoice.amount, Clock::get()?.unix_timestamp)?;
     msg!("Payment completed for vendor: {}", invoice.vendor_name);
+    emit!(PaymentCompleted {
+        org: invoice.org,
+        invoice: invoice.key(),
+        vendor: invoice.vendor,
+        amount: invoice.amount,
+        old_status,
+        new_status: invoice.status,
+    });
     Ok(())
 }
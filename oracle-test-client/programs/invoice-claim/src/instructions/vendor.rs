@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::events::*;
 use crate::state::*;
 
 
@@ -9,7 +10,7 @@ pub struct RegisterVendor<'info> {
         init,
         payer = authority,
         space = 8 + VendorAccount::INIT_SPACE,
-        seeds = [b"vendor", org_config.key().as_ref(), vendor_name.as_bytes()],
+        seeds = [b"vendor", org_config.key().as_ref(), &vendor_seed(&vendor_name)],
         bump
     )]
     pub vendor_account: Account<'info, VendorAccount>,
@@ -24,12 +25,12 @@ pub struct RegisterVendor<'info> {
     pub system_program: Program<'info, System>,
 }
 
-pub fn register_vendor(
+pub(crate) fn register_vendor(
     ctx: Context<RegisterVendor>,
     vendor_name: String,
     wallet: Pubkey,
 ) -> Result<()> {
-    require!(!vendor_name.is_empty(), InvoiceError::InvalidVendor);
+    require!(!normalize_vendor_name(&vendor_name).is_empty(), InvoiceError::InvalidVendor);
     require!(vendor_name.len() <= 50, InvoiceError::InvalidVendor);
     require!(wallet != Pubkey::default(), InvoiceError::InvalidWallet);
 
@@ -39,11 +40,117 @@ pub fn register_vendor(
         wallet,
         total_paid: 0,
         last_payment: 0,
+        payment_count: 0,
+        period_paid: 0,
+        current_period: 0,
         is_active: true,
         currency_preference: ctx.accounts.org_config.mint,
     });
 
     msg!("Vendor registered: {}", vendor_name);
+    emit!(vendor_changed(&ctx.accounts.vendor_account, VendorChange::Registered, Pubkey::default()));
+    Ok(())
+}
+
+// Moves a vendor registered under the raw-name seed to its hashed-seed PDA
+#[derive(Accounts)]
+#[instruction(vendor_name: String)]
+pub struct MigrateVendor<'info> {
+    /// CHECK: in the pre-counter layout, so owner and discriminator are checked when it is
+    /// read as a LegacyVendorAccount; closed by hand
+    #[account(
+        mut,
+        seeds = [b"vendor", org_config.key().as_ref(), vendor_name.as_bytes()],
+        bump
+    )]
+    pub legacy_vendor_account: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VendorAccount::INIT_SPACE,
+        seeds = [b"vendor", org_config.key().as_ref(), &vendor_seed(&vendor_name)],
+        bump
+    )]
+    pub vendor_account: Account<'info, VendorAccount>,
+
+    #[account(
+        has_one = authority @ InvoiceError::Unauthorized
+    )]
+    pub org_config: Account<'info, OrgConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Copies a legacy vendor and its lifetime totals to its hashed PDA and closes the old
+/// account. Invoices still pointing at the old address cannot be settled or paid until
+/// `migrate_invoice_vendor` moves them to the new one.
+pub(crate) fn migrate_vendor(ctx: Context<MigrateVendor>, vendor_name: String) -> Result<()> {
+    let legacy_info = ctx.accounts.legacy_vendor_account.to_account_info();
+    let legacy = LegacyVendorAccount::try_from_account(&legacy_info)?;
+    require_keys_eq!(legacy.org, ctx.accounts.org_config.key(), InvoiceError::WrongOrg);
+
+    ctx.accounts.vendor_account.set_inner(legacy.into_vendor());
+
+    // Return the legacy rent to the authority and hand the emptied account back to the system program
+    let authority = ctx.accounts.authority.to_account_info();
+    let rent = legacy_info.lamports();
+    **authority.try_borrow_mut_lamports()? = authority
+        .lamports()
+        .checked_add(rent)
+        .ok_or(InvoiceError::Overflow)?;
+    **legacy_info.try_borrow_mut_lamports()? = 0;
+    legacy_info.assign(&System::id());
+    legacy_info.resize(0)?;
+
+    msg!("Vendor migrated: {}", vendor_name);
+    emit!(vendor_changed(&ctx.accounts.vendor_account, VendorChange::Migrated, ctx.accounts.vendor_account.wallet));
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct MigrateInvoiceVendor<'info> {
+    #[account(
+        mut,
+        seeds = [b"invoice", org_config.key().as_ref(), &invoice_account.invoice_id.to_le_bytes()],
+        bump
+    )]
+    pub invoice_account: Account<'info, InvoiceAccount>,
+
+    // The migrated vendor the invoice was extracted for
+    #[account(
+        seeds = [b"vendor", org_config.key().as_ref(), &vendor_seed(&invoice_account.vendor_name)],
+        bump
+    )]
+    pub vendor_account: Account<'info, VendorAccount>,
+
+    #[account(
+        has_one = authority @ InvoiceError::Unauthorized
+    )]
+    pub org_config: Account<'info, OrgConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Points an invoice created against a legacy vendor at the vendor's migrated account,
+/// so it can be settled or paid again.
+pub(crate) fn migrate_invoice_vendor(ctx: Context<MigrateInvoiceVendor>) -> Result<()> {
+    let invoice = &mut ctx.accounts.invoice_account;
+    let vendor = ctx.accounts.vendor_account.key();
+    require_keys_neq!(invoice.vendor, vendor, InvoiceError::InvalidVendor);
+
+    let old_vendor = invoice.vendor;
+    invoice.vendor = vendor;
+
+    msg!("Invoice #{} now points at migrated vendor {}", invoice.invoice_id, vendor);
+    emit!(InvoiceVendorMigrated {
+        org: invoice.org,
+        invoice: invoice.key(),
+        old_vendor,
+        new_vendor: vendor,
+    });
     Ok(())
 }
 
@@ -51,7 +158,7 @@ pub fn register_vendor(
 pub struct ManageVendor<'info> {
     #[account(
         mut,
-        seeds = [b"vendor", org_config.key().as_ref(), vendor_account.vendor_name.as_bytes()],
+        seeds = [b"vendor", org_config.key().as_ref(), &vendor_seed(&vendor_account.vendor_name)],
         bump
     )]
     pub vendor_account: Account<'info, VendorAccount>,
@@ -64,31 +171,60 @@ pub struct ManageVendor<'info> {
     pub authority: Signer<'info>,
 }
 
-pub fn deactivate_vendor(ctx: Context<ManageVendor>) -> Result<()> {
+pub(crate) fn deactivate_vendor(ctx: Context<ManageVendor>) -> Result<()> {
     let vendor = &mut ctx.accounts.vendor_account;
     require!(vendor.is_active, InvoiceError::VendorInactive);
 
     vendor.is_active = false;
     msg!("Vendor deactivated: {}", vendor.vendor_name);
+    emit!(vendor_changed(vendor, VendorChange::Deactivated, vendor.wallet));
     Ok(())
 }
 
-pub fn activate_vendor(ctx: Context<ManageVendor>) -> Result<()> {
+pub(crate) fn activate_vendor(ctx: Context<ManageVendor>) -> Result<()> {
     let vendor = &mut ctx.accounts.vendor_account;
     require!(!vendor.is_active, InvoiceError::VendorInactive);
 
     vendor.is_active = true;
     msg!("Vendor activated: {}", vendor.vendor_name);
+    emit!(vendor_changed(vendor, VendorChange::Activated, vendor.wallet));
     Ok(())
 }
 
-pub fn update_vendor_wallet(
+pub(crate) fn update_vendor_wallet(
     ctx: Context<ManageVendor>,
     new_wallet: Pubkey,
 ) -> Result<()> {
     require!(new_wallet != Pubkey::default(), InvoiceError::InvalidWallet);
     let vendor = &mut ctx.accounts.vendor_account;
+    let old_wallet = vendor.wallet;
     vendor.wallet = new_wallet;
     msg!("Vendor wallet updated for: {}", vendor.vendor_name);
+    emit!(vendor_changed(vendor, VendorChange::WalletUpdated, old_wallet));
     Ok(())
 }
+
+fn vendor_changed(vendor: &Account<VendorAccount>, change: VendorChange, old_wallet: Pubkey) -> VendorChanged {
+    VendorChanged {
+        org: vendor.org,
+        vendor: vendor.key(),
+        vendor_name: vendor.vendor_name.clone(),
+        change,
+        old_wallet,
+        new_wallet: vendor.wallet,
+        is_active: vendor.is_active,
+    }
+}
+
+#[derive(Accounts)]
+pub struct VendorPeriodPaid<'info> {
+    pub vendor_account: Account<'info, VendorAccount>,
+}
+
+/// Read-only: how much this vendor has been paid in the current UTC month. Call via simulation.
+pub(crate) fn get_vendor_period_paid(ctx: Context<VendorPeriodPaid>) -> Result<u64> {
+    let vendor = &ctx.accounts.vendor_account;
+    let paid = vendor.paid_in_period(Clock::get()?.unix_timestamp);
+    msg!("Paid to {} this month: {} over {} payments lifetime", vendor.vendor_name, paid, vendor.payment_count);
+    Ok(paid)
+}
@@ -3,10 +3,11 @@ use ephemeral_vrf_sdk::anchor::vrf;
 use ephemeral_vrf_sdk::instructions::{create_request_randomness_ix, RequestRandomnessParams};
 use ephemeral_vrf_sdk::types::SerializableAccountMeta;
 use crate::CALLBACK_VRF_DISCRIMINATOR;
+use crate::events::*;
 use crate::state::*;
 
     /// STEP 1: Request randomness to decide if invoice should be audited
-    pub fn request_invoice_audit_vrf(ctx: Context<RequestInvoiceAuditVrf>, client_seed: u8) -> Result<()> {
+    pub(crate) fn request_invoice_audit_vrf(ctx: Context<RequestInvoiceAuditVrf>, client_seed: u8) -> Result<()> {
         let invoice = &ctx.accounts.invoice_account;
 
         require!(
@@ -47,7 +48,7 @@ use crate::state::*;
     }
 
     /// STEP 2: Callback once randomness is ready
-pub fn callback_invoice_vrf(ctx: Context<CallbackInvoiceVrf>, randomness: [u8; 32]) -> Result<()> {
+pub(crate) fn callback_invoice_vrf(ctx: Context<CallbackInvoiceVrf>, randomness: [u8; 32]) -> Result<()> {
     msg!("Received VRF callback |deciding audit outcome...");
 
     let invoice = &mut ctx.accounts.invoice_account;
@@ -68,6 +69,7 @@ pub fn callback_invoice_vrf(ctx: Context<CallbackInvoiceVrf>, randomness: [u8; 3
         let audit_selected = random_value % 10_000 < threshold;
 
         // Update status
+        let old_status = invoice.status;
         invoice.status = if audit_selected {
             InvoiceStatus::AuditPending
         } else {
@@ -81,6 +83,14 @@ pub fn callback_invoice_vrf(ctx: Context<CallbackInvoiceVrf>, randomness: [u8; 3
             random_value,
             threshold
         );
+        emit!(AuditSelected {
+            org: org_config.key(),
+            invoice: invoice.key(),
+            selected: audit_selected,
+            audit_rate_bps: org_config.audit_rate_bps,
+            old_status,
+            new_status: invoice.status,
+        });
         Ok(())
     }
 
@@ -94,13 +104,14 @@ pub struct RequestInvoiceAuditVrf<'info> {
     #[account(
         mut,
         seeds = [b"org_config", org_config.authority.as_ref()],
-        bump
+        bump,
+        address = invoice_account.org @ InvoiceError::WrongOrg
     )]
     pub org_config: Account<'info, OrgConfig>,
 
     #[account(
         mut,
-        seeds = [b"invoice", invoice_account.authority.as_ref()],
+        seeds = [b"invoice", invoice_account.org.as_ref(), &invoice_account.invoice_id.to_le_bytes()],
         bump
     )]
     pub invoice_account: Account<'info, InvoiceAccount>,
@@ -121,6 +132,7 @@ pub struct CallbackInvoiceVrf<'info> {
     // 1) invoice_account (writable)
     #[account(mut)]
     pub invoice_account: Account<'info, InvoiceAccount>,
-    // 2) org_config (readonly)
+    // 2) org_config (readonly); must be the org the invoice was issued under
+    #[account(address = invoice_account.org @ InvoiceError::WrongOrg)]
     pub org_config: Account<'info, OrgConfig>,
 }
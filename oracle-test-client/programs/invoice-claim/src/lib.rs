@@ -7,9 +7,18 @@ declare_id!("CwD9tU4A7c7SS5b55ZtTcEPGA8svJQUhfdCbdoaSF1Tx");
 pub const CALLBACK_VRF_DISCRIMINATOR: [u8; 7] = *b"clbrand"; 
 mod state;
 mod instructions;
+pub mod events;
 
 pub use crate::state::*;
-pub use crate::instructions::*;
+// Handlers share their names with the #[program] entry points, so only the account
+// contexts are public; the glob brings Anchor's generated client modules into scope
+use crate::instructions::*;
+pub use crate::instructions::{
+    AuditDecide, CallbackInvoiceVrf, CancelInvoice, CloseInvoice, CloseRequest, CompletePayment,
+    DailyHeadroom, FundEscrow, ManageVendor, MigrateInvoiceVendor, MigrateOrgConfig, MigrateVendor,
+    OrgInit, ProcessPayment, ProcessResult, RefundEscrow, RegisterVendor, RejectExtraction,
+    RequestExtraction, RequestInvoiceAuditVrf, SettleToVendor, UpdateOrgConfig, VendorPeriodPaid,
+};
 
 
 #[program]
@@ -17,6 +26,7 @@ pub mod invoice_claim {
     use super::*;
 
     // Invoice request + OCR fulfillment
+    /// `amount` is the claimed total in base units of the org's mint (e.g. 40_000_000 for 40.00 at 6 decimals)
     pub fn request_invoice_extraction(ctx: Context<RequestExtraction>, ipfs_hash: String, amount: u64) -> Result<()> {
         instructions::invoice::request_invoice_extraction(ctx, ipfs_hash,amount)
     }
@@ -26,8 +36,14 @@ pub mod invoice_claim {
         vendor_name: String,
         amount: u64,
         due_date: i64,
+        invoice_number: String,
+        confidence_bps: u16,
     ) -> Result<()> {
-        instructions::invoice::process_extraction_result(ctx, vendor_name, amount, due_date)
+        instructions::invoice::process_extraction_result(ctx, vendor_name, amount, due_date, invoice_number, confidence_bps)
+    }
+
+    pub fn reject_extraction(ctx: Context<RejectExtraction>, reason: RejectReason) -> Result<()> {
+        instructions::invoice::reject_extraction(ctx, reason)
     }
 
     pub fn request_invoice_audit_vrf(ctx: Context<RequestInvoiceAuditVrf>, client_seed: u8) -> Result<()> {
@@ -48,6 +64,15 @@ pub mod invoice_claim {
         instructions::payments::complete_payment(ctx)
     }
 
+    // Cancellation and escrow refunds
+    pub fn cancel_invoice(ctx: Context<CancelInvoice>) -> Result<()> {
+        instructions::cancel::cancel_invoice(ctx)
+    }
+
+    pub fn refund_escrow(ctx: Context<RefundEscrow>) -> Result<()> {
+        instructions::cancel::refund_escrow(ctx)
+    }
+
     // Close accounts
     pub fn close_invoice(ctx: Context<CloseInvoice>) -> Result<()> {
         instructions::close::close_invoice(ctx)
@@ -58,8 +83,13 @@ pub mod invoice_claim {
     }
 
     // Manual review decision after VRF selects invoice for audit
-    pub fn audit_decide(ctx: Context<AuditDecide>, approve: bool) -> Result<()> {
-        instructions::invoice::audit_decide(ctx, approve)
+    pub fn audit_decide(
+        ctx: Context<AuditDecide>,
+        approve: bool,
+        reason: AuditReason,
+        evidence_hash: String,
+    ) -> Result<()> {
+        instructions::invoice::audit_decide(ctx, approve, reason, evidence_hash)
     }
 
     // Org config
@@ -74,11 +104,28 @@ pub mod invoice_claim {
         instructions::org::org_init(ctx, treasury_vault, mint, per_invoice_cap, daily_cap, audit_rate_bps)
     }
 
+    pub fn migrate_org_config(ctx: Context<MigrateOrgConfig>) -> Result<()> {
+        instructions::org::migrate_org_config(ctx)
+    }
+
     // Update Org Config
     pub fn update_org_config(ctx: Context<UpdateOrgConfig>, update_args: UpdateOrgConfigArgs) -> Result<()> {
         instructions::org::update_org_config(ctx, update_args)
     }
 
+    // Auditors decide flagged invoices; kept separate from the oracle signer
+    pub fn add_auditor(ctx: Context<UpdateOrgConfig>, auditor: Pubkey) -> Result<()> {
+        instructions::org::add_auditor(ctx, auditor)
+    }
+
+    pub fn remove_auditor(ctx: Context<UpdateOrgConfig>, auditor: Pubkey) -> Result<()> {
+        instructions::org::remove_auditor(ctx, auditor)
+    }
+
+    pub fn get_daily_headroom(ctx: Context<DailyHeadroom>) -> Result<u64> {
+        instructions::org::get_daily_headroom(ctx)
+    }
+
     // Escrow MVP
     pub fn fund_escrow(ctx: Context<FundEscrow>) -> Result<()> {
         instructions::escrow::fund_escrow(ctx)
@@ -97,6 +144,14 @@ pub mod invoice_claim {
         instructions::vendor::register_vendor(ctx, vendor_name, wallet)
     }
 
+    pub fn migrate_vendor(ctx: Context<MigrateVendor>, vendor_name: String) -> Result<()> {
+        instructions::vendor::migrate_vendor(ctx, vendor_name)
+    }
+
+    pub fn migrate_invoice_vendor(ctx: Context<MigrateInvoiceVendor>) -> Result<()> {
+        instructions::vendor::migrate_invoice_vendor(ctx)
+    }
+
     pub fn deactivate_vendor(ctx: Context<ManageVendor>) -> Result<()> {
         instructions::vendor::deactivate_vendor(ctx)
     }
@@ -109,5 +164,9 @@ pub mod invoice_claim {
         instructions::vendor::update_vendor_wallet(ctx, new_wallet)
     }
 
+    pub fn get_vendor_period_paid(ctx: Context<VendorPeriodPaid>) -> Result<u64> {
+        instructions::vendor::get_vendor_period_paid(ctx)
+    }
+
 
 }
@@ -1,29 +1,54 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::{hash, hashv};
 
 #[account]
 #[derive(InitSpace)]
 pub struct InvoiceRequest {
     pub authority: Pubkey,
+    pub org: Pubkey,                    // OrgConfig whose counter issued invoice_id
+    pub invoice_id: u64,                // Seeds this request and its invoice
     #[max_len(64)]
     pub ipfs_hash: String,
     pub status: RequestStatus,
     pub timestamp: i64,
-    pub amount: u64,
+    pub amount: u64,                    // Claimed total, in base units of OrgConfig.mint
+    pub reject_reason: Option<RejectReason>, // Set when the oracle marks the request Failed
 }
 
 #[account]
 #[derive(InitSpace)]
 pub struct InvoiceAccount {
     pub authority: Pubkey,              // Invoice owner
+    pub org: Pubkey,                    // OrgConfig whose counter issued invoice_id
+    pub invoice_id: u64,                // Same id as the originating request
     pub vendor: Pubkey,                 // Links to VendorAccount
     #[max_len(50)]
     pub vendor_name: String,
-    pub amount: u64,
+    #[max_len(32)]
+    pub invoice_number: String,         // Seller's invoice number as printed
+    pub amount: u64,                    // Extracted from the document by the oracle
+    pub claimed_amount: u64,            // Declared by the submitter on the request
+    pub confidence_bps: u16,            // Oracle's confidence in the extracted fields
     pub due_date: i64,
     #[max_len(64)]
     pub ipfs_hash: String,
     pub status: InvoiceStatus,
     pub timestamp: i64,
+    pub escrow_funder: Pubkey,          // Owner of the token account that funded the escrow, refunded on cancel
+    pub funded_at: i64,                 // When fund_escrow counted the amount against the daily cap
+    pub reviewer: Pubkey,               // Auditor who decided a flagged invoice; default if never audited
+    pub reviewed_at: i64,               // Unix timestamp of the audit decision
+}
+
+impl InvoiceAccount {
+    /// Owner of the tokens left in the escrow: whoever funded it, or the invoice owner if nobody did.
+    pub fn escrow_recipient(&self) -> Pubkey {
+        if self.escrow_funder == Pubkey::default() {
+            self.authority
+        } else {
+            self.escrow_funder
+        }
+    }
 }
 
 //A singleton state that manages the full protocol
@@ -32,6 +57,8 @@ pub struct InvoiceAccount {
 pub struct OrgConfig {
     pub authority: Pubkey,
     pub oracle_signer: Pubkey,
+    #[max_len(MAX_AUDITORS)]
+    pub auditors: Vec<Pubkey>,          // May decide flagged invoices; never the oracle_signer
     pub treasury_vault: Pubkey,
     pub mint: Pubkey,
     pub per_invoice_cap: u64,
@@ -39,30 +66,307 @@ pub struct OrgConfig {
     pub daily_spent: u64,               // Track daily spending
     pub last_reset_day: i64,            // Last day caps were reset
     pub audit_rate_bps: u16,            // Basis points (e.g., 500 = 5%)
+    pub amount_tolerance_bps: u16,      // Allowed claimed vs extracted gap; wider goes to audit
+    pub min_confidence_bps: u16,        // Extractions the oracle is less sure of go to audit; 0 disables
     pub paused: bool,
-    pub invoice_counter: u64,
+    pub invoice_counter: u64,           // Next invoice_id handed out by request_invoice_extraction
     pub version: u8,
     pub bump: u8
 }
 
+const SECONDS_PER_DAY: i64 = 86_400;
+
+pub const MAX_AUDITORS: usize = 5;
+
+/// `OrgConfig.version` of the current layout; 1 is `LegacyOrgConfig`.
+pub const ORG_CONFIG_VERSION: u8 = 2;
+
+impl OrgConfig {
+    pub fn is_auditor(&self, key: &Pubkey) -> bool {
+        self.auditors.contains(key)
+    }
+
+    /// True when `extracted` is within `amount_tolerance_bps` of the submitter's `claimed` amount.
+    pub fn amount_within_tolerance(&self, claimed: u64, extracted: u64) -> bool {
+        let diff = u128::from(claimed.abs_diff(extracted));
+        diff * 10_000 <= u128::from(claimed) * u128::from(self.amount_tolerance_bps)
+    }
+
+    /// Amount that may still leave the treasury during the UTC day containing `now`.
+    pub fn daily_headroom(&self, now: i64) -> u64 {
+        if now / SECONDS_PER_DAY != self.last_reset_day {
+            return self.daily_cap;
+        }
+        self.daily_cap.saturating_sub(self.daily_spent)
+    }
+
+    /// Counts `amount` against today's cap, starting a fresh window on a new UTC day.
+    pub fn record_daily_spend(&mut self, amount: u64, now: i64) -> Result<()> {
+        let today = now / SECONDS_PER_DAY;
+        if today != self.last_reset_day {
+            self.daily_spent = 0;
+            self.last_reset_day = today;
+        }
+
+        let spent = self.daily_spent.checked_add(amount).ok_or(InvoiceError::Overflow)?;
+        require!(spent <= self.daily_cap, InvoiceError::DailyCapExceeded);
+        self.daily_spent = spent;
+        Ok(())
+    }
+
+    /// Takes back `amount` recorded at `spent_at` if that was in the window still open at `now`;
+    /// spending from an earlier day already rolled off.
+    pub fn release_daily_spend(&mut self, amount: u64, spent_at: i64, now: i64) {
+        let today = now / SECONDS_PER_DAY;
+        if spent_at / SECONDS_PER_DAY == today && self.last_reset_day == today {
+            self.daily_spent = self.daily_spent.saturating_sub(amount);
+        }
+    }
+}
+
+
+// OrgConfig as created before auditors, the amount tolerance and the confidence threshold;
+// only read by migrate_org_config. Shares OrgConfig's discriminator.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct LegacyOrgConfig {
+    pub authority: Pubkey,
+    pub oracle_signer: Pubkey,
+    pub treasury_vault: Pubkey,
+    pub mint: Pubkey,
+    pub per_invoice_cap: u64,
+    pub daily_cap: u64,
+    pub daily_spent: u64,
+    pub last_reset_day: i64,
+    pub audit_rate_bps: u16,
+    pub paused: bool,
+    pub invoice_counter: u64,
+    pub version: u8,
+    pub bump: u8,
+}
+
+impl LegacyOrgConfig {
+    /// The org in the current layout, with no auditors and the checks the new fields
+    /// add turned off, as `org_init` leaves them.
+    pub fn into_org_config(self) -> OrgConfig {
+        OrgConfig {
+            authority: self.authority,
+            oracle_signer: self.oracle_signer,
+            auditors: Vec::new(),
+            treasury_vault: self.treasury_vault,
+            mint: self.mint,
+            per_invoice_cap: self.per_invoice_cap,
+            daily_cap: self.daily_cap,
+            daily_spent: self.daily_spent,
+            last_reset_day: self.last_reset_day,
+            audit_rate_bps: self.audit_rate_bps,
+            amount_tolerance_bps: 0,
+            min_confidence_bps: 0,
+            paused: self.paused,
+            invoice_counter: self.invoice_counter,
+            version: ORG_CONFIG_VERSION,
+            bump: self.bump,
+        }
+    }
+
+    /// Reads a legacy org after checking it is an OrgConfig of this program still sized
+    /// for the old layout.
+    pub fn try_from_account(info: &AccountInfo) -> Result<Self> {
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+        let data = info.try_borrow_data()?;
+        require!(
+            data.starts_with(OrgConfig::DISCRIMINATOR),
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        // A migrated org is larger; its bytes would also read as the old layout
+        require!(
+            data.len() == OrgConfig::DISCRIMINATOR.len() + Self::INIT_SPACE,
+            ErrorCode::AccountDidNotDeserialize
+        );
+        Self::deserialize(&mut &data[OrgConfig::DISCRIMINATOR.len()..])
+            .map_err(|_| error!(ErrorCode::AccountDidNotDeserialize))
+    }
+}
 
 #[account]
 #[derive(InitSpace)]
 pub struct VendorAccount {
     pub org: Pubkey,                    // Links to OrgConfig
     #[max_len(50)]
-    pub vendor_name: String,            // Vendor identifier (matches invoice.vendor_name)
+    pub vendor_name: String,            // Display name; the PDA is seeded by vendor_seed(vendor_name)
     pub wallet: Pubkey,                 // Where to send payments
     pub total_paid: u64,                // Lifetime payment tracking
     pub last_payment: i64,              // Unix timestamp of last payment
+    pub payment_count: u64,             // Lifetime number of payouts
+    pub period_paid: u64,               // Paid during current_period
+    pub current_period: i64,            // UTC calendar month of period_paid, as months since 1970-01
     pub is_active: bool,                // Can be disabled to block payments
     pub currency_preference: Pubkey,    // Preferred mint (for multi-currency)
 }
 
+// VendorAccount as registered under the raw-name seed, before payment counts and monthly
+// totals; only read by migrate_vendor. Shares VendorAccount's discriminator.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyVendorAccount {
+    pub org: Pubkey,
+    pub vendor_name: String,
+    pub wallet: Pubkey,
+    pub total_paid: u64,
+    pub last_payment: i64,
+    pub is_active: bool,
+    pub currency_preference: Pubkey,
+}
+
+impl LegacyVendorAccount {
+    /// The vendor in the current layout; the old one never counted payouts or tracked a
+    /// monthly period, so those start from zero.
+    pub fn into_vendor(self) -> VendorAccount {
+        VendorAccount {
+            org: self.org,
+            vendor_name: self.vendor_name,
+            wallet: self.wallet,
+            total_paid: self.total_paid,
+            last_payment: self.last_payment,
+            payment_count: 0,
+            period_paid: 0,
+            current_period: 0,
+            is_active: self.is_active,
+            currency_preference: self.currency_preference,
+        }
+    }
+
+    /// Reads a legacy vendor after checking it is a VendorAccount owned by this program.
+    pub fn try_from_account(info: &AccountInfo) -> Result<Self> {
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+        let data = info.try_borrow_data()?;
+        require!(
+            data.starts_with(VendorAccount::DISCRIMINATOR),
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        // The account was sized for a 50-byte name, so a shorter one leaves zero padding
+        Self::deserialize(&mut &data[VendorAccount::DISCRIMINATOR.len()..])
+            .map_err(|_| error!(ErrorCode::AccountDidNotDeserialize))
+    }
+}
+
+impl VendorAccount {
+    /// Amount paid to this vendor during the UTC calendar month containing `now`.
+    pub fn paid_in_period(&self, now: i64) -> u64 {
+        if month_index(now) != self.current_period {
+            return 0;
+        }
+        self.period_paid
+    }
+
+    /// Adds a payout to the lifetime and monthly totals, starting a fresh period on a new month.
+    pub fn record_payment(&mut self, amount: u64, now: i64) -> Result<()> {
+        let period = month_index(now);
+        if period != self.current_period {
+            self.period_paid = 0;
+            self.current_period = period;
+        }
+
+        self.total_paid = self.total_paid.checked_add(amount).ok_or(InvoiceError::Overflow)?;
+        self.period_paid = self.period_paid.checked_add(amount).ok_or(InvoiceError::Overflow)?;
+        self.payment_count = self.payment_count.checked_add(1).ok_or(InvoiceError::Overflow)?;
+        self.last_payment = now;
+        Ok(())
+    }
+}
+
+/// Months since 1970-01 for a unix timestamp, using the proleptic Gregorian calendar.
+fn month_index(now: i64) -> i64 {
+    // Civil-from-days (Howard Hinnant), with the year starting in March
+    let z = now.div_euclid(SECONDS_PER_DAY) + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year - 1970) * 12 + (month - 1)
+}
+
+// Marks a document or (vendor, invoice number, amount) as already turned into an invoice.
+// Never closed, so the same invoice cannot be paid again after its accounts are closed.
+#[account]
+#[derive(InitSpace)]
+pub struct InvoiceRegistry {
+    pub org: Pubkey,
+    pub invoice: Pubkey,                // Default until claimed by process_extraction_result
+    pub created_at: i64,
+}
+
+/// Trimmed, ASCII-lowercased and with inner whitespace collapsed, so "Acme  Corp" and
+/// "acme corp" are the same vendor.
+pub fn normalize_vendor_name(vendor_name: &str) -> String {
+    vendor_name
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_ascii_lowercase()
+}
+
+/// Seed for the `[b"vendor", org, ..]` PDA. Hashing lifts the 32-byte seed limit, so
+/// any name `register_vendor` accepts can be derived.
+pub fn vendor_seed(vendor_name: &str) -> [u8; 32] {
+    hash(normalize_vendor_name(vendor_name).as_bytes()).to_bytes()
+}
+
+/// Seed for the `[b"doc_hash", org, ..]` registry of an IPFS CID.
+pub fn document_hash(ipfs_hash: &str) -> [u8; 32] {
+    hash(ipfs_hash.trim().as_bytes()).to_bytes()
+}
+
+/// Uppercase ASCII letters and digits only, so "inv-0042" and "INV 0042" compare equal.
+pub fn normalize_invoice_number(invoice_number: &str) -> String {
+    invoice_number
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// Seed for the `[b"fingerprint", org, ..]` registry; the vendor PDA stands in for its name.
+pub fn invoice_fingerprint(vendor: &Pubkey, invoice_number: &str, amount: u64) -> [u8; 32] {
+    hashv(&[
+        vendor.as_ref(),
+        normalize_invoice_number(invoice_number).as_bytes(),
+        &amount.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+// Why a flagged invoice was paid or rejected, one per invoice
+#[account]
+#[derive(InitSpace)]
+pub struct AuditRecord {
+    pub org: Pubkey,
+    pub invoice: Pubkey,
+    pub reviewer: Pubkey,
+    pub approved: bool,
+    pub reason: AuditReason,
+    #[max_len(64)]
+    pub evidence_hash: String,          // IPFS CID or hex digest of the supporting evidence
+    pub decided_at: i64,
+    pub bump: u8,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
 pub enum RequestStatus {
     Pending,
     Completed,
+    Failed,
+}
+
+// Why the oracle could not turn a request into an invoice
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum RejectReason {
+    OcrFailure,
+    UnknownVendor,
+    CapExceeded,
+    InvalidDueDate,
+    Duplicate,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
@@ -73,6 +377,18 @@ pub enum InvoiceStatus {
     InEscrow,
     Paid,
     Rejected,
+    Cancelled,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum AuditReason {
+    Verified,
+    AmountMismatch,
+    VendorMismatch,
+    DuplicateInvoice,
+    MissingDocumentation,
+    SuspectedFraud,
+    Other,
 }
 
 // Update Org Config Args
@@ -82,6 +398,8 @@ pub struct UpdateOrgConfigArgs {
     pub daily_cap: Option<u64>,
     pub paused: Option<bool>,
     pub oracle_signer: Option<Pubkey>,
+    pub amount_tolerance_bps: Option<u16>,
+    pub min_confidence_bps: Option<u16>,
 }
 
 #[error_code]
@@ -118,4 +436,242 @@ pub enum InvoiceError {
     InvalidWallet,
     #[msg("Invalid IPFS hash")]
     InvalidIPFSHash,
+    #[msg("Daily spending cap exceeded")]
+    DailyCapExceeded,
+    #[msg("Invoice has funds in escrow; refund or settle it first")]
+    EscrowActive,
+    #[msg("Escrow token account still holds a balance and no account was given to sweep it to")]
+    EscrowNotEmpty,
+    #[msg("Signer is not an auditor for this org")]
+    NotAuditor,
+    #[msg("The oracle signer cannot act as an auditor")]
+    OracleCannotAudit,
+    #[msg("Auditor list is full")]
+    TooManyAuditors,
+    #[msg("Auditor is already registered")]
+    DuplicateAuditor,
+    #[msg("Invalid audit evidence hash")]
+    InvalidEvidence,
+    #[msg("This invoice has already been processed")]
+    DuplicateInvoice,
+    #[msg("Invalid invoice number")]
+    InvalidInvoiceNumber,
+    #[msg("Amount tolerance must be at most 10000 bps")]
+    InvalidTolerance,
+    #[msg("Confidence must be at most 10000 bps")]
+    InvalidConfidence,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = SECONDS_PER_DAY;
+
+    fn org_config(daily_cap: u64, amount_tolerance_bps: u16) -> OrgConfig {
+        OrgConfig {
+            authority: Pubkey::new_unique(),
+            oracle_signer: Pubkey::new_unique(),
+            auditors: Vec::new(),
+            treasury_vault: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            per_invoice_cap: daily_cap,
+            daily_cap,
+            daily_spent: 0,
+            last_reset_day: 0,
+            audit_rate_bps: 0,
+            amount_tolerance_bps,
+            min_confidence_bps: 0,
+            paused: false,
+            invoice_counter: 0,
+            version: ORG_CONFIG_VERSION,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn daily_spend_is_capped_within_a_utc_day() {
+        let mut cfg = org_config(100, 0);
+        let morning = 20_000 * DAY + 60;
+
+        cfg.record_daily_spend(70, morning).unwrap();
+        assert_eq!(cfg.daily_headroom(morning + 3_600), 30);
+        assert!(cfg.record_daily_spend(31, morning + 3_600).is_err());
+        assert_eq!(cfg.daily_spent, 70);
+
+        cfg.record_daily_spend(30, morning + 3_600).unwrap();
+        assert_eq!(cfg.daily_headroom(morning + 3_600), 0);
+    }
+
+    #[test]
+    fn daily_spend_resets_on_the_next_utc_day() {
+        let mut cfg = org_config(100, 0);
+        let last_second = 20_001 * DAY - 1;
+
+        cfg.record_daily_spend(100, last_second).unwrap();
+        assert_eq!(cfg.daily_headroom(last_second), 0);
+        // Headroom is fresh before anything is recorded on the new day
+        assert_eq!(cfg.daily_headroom(last_second + 1), 100);
+
+        cfg.record_daily_spend(40, last_second + 1).unwrap();
+        assert_eq!((cfg.daily_spent, cfg.last_reset_day), (40, 20_001));
+    }
+
+    #[test]
+    fn amount_tolerance_is_relative_to_the_claimed_amount() {
+        let exact = org_config(0, 0);
+        assert!(exact.amount_within_tolerance(1_000, 1_000));
+        assert!(!exact.amount_within_tolerance(1_000, 999));
+
+        // 1% either way, inclusive
+        let cfg = org_config(0, 100);
+        assert!(cfg.amount_within_tolerance(40_000_000, 40_400_000));
+        assert!(cfg.amount_within_tolerance(40_000_000, 39_600_000));
+        assert!(!cfg.amount_within_tolerance(40_000_000, 40_400_001));
+        // Nothing is within a percentage of a zero claim but zero itself
+        assert!(cfg.amount_within_tolerance(0, 0));
+        assert!(!cfg.amount_within_tolerance(0, 1));
+        // Widened to u128, so the largest amounts do not overflow
+        assert!(org_config(0, 10_000).amount_within_tolerance(u64::MAX, 0));
+    }
+
+    #[test]
+    fn refunds_only_release_spend_from_the_open_day() {
+        let mut cfg = org_config(100, 0);
+        let yesterday = 20_000 * DAY + 600;
+        let today = yesterday + DAY;
+
+        cfg.record_daily_spend(60, yesterday).unwrap();
+        cfg.record_daily_spend(30, today).unwrap();
+        cfg.release_daily_spend(60, yesterday, today);
+        assert_eq!(cfg.daily_spent, 30);
+
+        cfg.release_daily_spend(50, today, today + 60);
+        assert_eq!(cfg.daily_spent, 0);
+    }
+
+    fn legacy_vendor_account(org: Pubkey) -> LegacyVendorAccount {
+        LegacyVendorAccount {
+            org,
+            vendor_name: "Acme Corp".to_string(),
+            wallet: Pubkey::new_unique(),
+            total_paid: 1_500,
+            last_payment: 1_700_000_000,
+            is_active: true,
+            currency_preference: Pubkey::new_unique(),
+        }
+    }
+
+    // A legacy vendor as stored on-chain: discriminator, old layout, zero padding
+    fn legacy_vendor_data(org: Pubkey) -> Vec<u8> {
+        let mut data = VendorAccount::DISCRIMINATOR.to_vec();
+        legacy_vendor_account(org).serialize(&mut data).unwrap();
+        data.resize(data.len() + 41, 0);
+        data
+    }
+
+    #[test]
+    fn month_index_counts_calendar_months_since_1970() {
+        assert_eq!(month_index(0), 0);
+        assert_eq!(month_index(-1), -1);
+        // 1999-12-31T23:59:59Z and 2000-01-01T00:00:00Z
+        assert_eq!(month_index(946_684_799), 29 * 12 + 11);
+        assert_eq!(month_index(946_684_800), 30 * 12);
+        // 2024-02-29, a leap day
+        assert_eq!(month_index(1_709_164_800), 54 * 12 + 1);
+        assert_eq!(month_index(1_709_164_800 + DAY), 54 * 12 + 2);
+    }
+
+    #[test]
+    fn vendor_period_total_restarts_each_month() {
+        let mut vendor = legacy_vendor_account(Pubkey::new_unique()).into_vendor();
+        let leap_day = 1_709_164_800;
+
+        vendor.record_payment(300, leap_day).unwrap();
+        vendor.record_payment(200, leap_day + 60).unwrap();
+        assert_eq!(vendor.paid_in_period(leap_day + 120), 500);
+        assert_eq!(vendor.paid_in_period(leap_day + DAY), 0);
+
+        vendor.record_payment(50, leap_day + DAY).unwrap();
+        assert_eq!(vendor.paid_in_period(leap_day + DAY), 50);
+        assert_eq!((vendor.total_paid, vendor.payment_count), (1_500 + 550, 3));
+        assert_eq!(vendor.last_payment, leap_day + DAY);
+    }
+
+    fn legacy_org_config() -> LegacyOrgConfig {
+        LegacyOrgConfig {
+            authority: Pubkey::new_unique(),
+            oracle_signer: Pubkey::new_unique(),
+            treasury_vault: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            per_invoice_cap: 1_000,
+            daily_cap: 10_000,
+            daily_spent: 250,
+            last_reset_day: 19_700,
+            audit_rate_bps: 500,
+            paused: false,
+            invoice_counter: 7,
+            version: 1,
+            bump: 254,
+        }
+    }
+
+    #[test]
+    fn legacy_org_config_migrates_with_new_checks_off() {
+        let legacy = legacy_org_config();
+        let (key, authority) = (Pubkey::new_unique(), legacy.authority);
+        let mut data = OrgConfig::DISCRIMINATOR.to_vec();
+        legacy.serialize(&mut data).unwrap();
+        let mut lamports = 1_000_000;
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &crate::ID, false, 0);
+
+        let cfg = LegacyOrgConfig::try_from_account(&info).unwrap().into_org_config();
+        assert_eq!(cfg.authority, authority);
+        assert_eq!((cfg.daily_spent, cfg.invoice_counter, cfg.bump), (250, 7, 254));
+        assert!(cfg.auditors.is_empty());
+        assert_eq!((cfg.amount_tolerance_bps, cfg.min_confidence_bps), (0, 0));
+        assert_eq!(cfg.version, ORG_CONFIG_VERSION);
+    }
+
+    #[test]
+    fn migrated_org_config_is_not_read_as_legacy() {
+        let key = Pubkey::new_unique();
+        let mut data = Vec::new();
+        legacy_org_config().into_org_config().try_serialize(&mut data).unwrap();
+        data.resize(8 + OrgConfig::INIT_SPACE, 0);
+        let mut lamports = 1_000_000;
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &crate::ID, false, 0);
+        assert!(LegacyOrgConfig::try_from_account(&info).is_err());
+    }
+
+    #[test]
+    fn legacy_vendor_migrates_with_fresh_counters() {
+        let (key, org) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = legacy_vendor_data(org);
+        let mut lamports = 1_000_000;
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &crate::ID, false, 0);
+
+        let vendor = LegacyVendorAccount::try_from_account(&info).unwrap().into_vendor();
+        assert_eq!(vendor.org, org);
+        assert_eq!(vendor.vendor_name, "Acme Corp");
+        assert_eq!((vendor.total_paid, vendor.last_payment), (1_500, 1_700_000_000));
+        assert_eq!((vendor.payment_count, vendor.period_paid, vendor.current_period), (0, 0, 0));
+        assert!(vendor.is_active);
+    }
+
+    #[test]
+    fn legacy_vendor_must_be_a_vendor_of_this_program() {
+        let key = Pubkey::new_unique();
+        let mut lamports = 1_000_000;
+
+        let mut data = legacy_vendor_data(Pubkey::new_unique());
+        let other_program = Pubkey::new_unique();
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &other_program, false, 0);
+        assert!(LegacyVendorAccount::try_from_account(&info).is_err());
+
+        let mut data = legacy_vendor_data(Pubkey::new_unique());
+        data[..8].copy_from_slice(OrgConfig::DISCRIMINATOR);
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &crate::ID, false, 0);
+        assert!(LegacyVendorAccount::try_from_account(&info).is_err());
+    }
 }
@@ -1,4 +1,11 @@
 import * as anchor from "@coral-xyz/anchor";
+import { createHash } from "crypto";
+
+// Vendor PDAs are seeded by sha256 of the normalized name, matching `vendor_seed` on-chain
+const vendorSeed = (name: string) =>
+  createHash("sha256")
+    .update(name.trim().split(/\s+/).join(" ").replace(/[A-Z]/g, (c) => c.toLowerCase()))
+    .digest();
 
 async function main() {
   const provider = anchor.AnchorProvider.env();
@@ -44,6 +51,10 @@ async function main() {
           perInvoiceCap: null,
           dailyCap: null,
           paused: null,
+          // Claimed vs extracted amount gap allowed before an invoice goes to audit
+          amountToleranceBps: process.env.AMOUNT_TOLERANCE_BPS ? parseInt(process.env.AMOUNT_TOLERANCE_BPS, 10) : null,
+          // Extractions the oracle scores below this go to audit instead of the VRF draw
+          minConfidenceBps: process.env.MIN_CONFIDENCE_BPS ? parseInt(process.env.MIN_CONFIDENCE_BPS, 10) : null,
         })
         .accounts({ authority: wallet.publicKey, orgConfig: orgConfigPda })
         .rpc();
@@ -55,10 +66,27 @@ async function main() {
     console.log("ORACLE_PUBKEY not provided; skipping oracle_signer update.");
   }
 
-  // 3) Register a vendor (matches what OCR likely returns)
+  // 3) Register an auditor for flagged invoices; must differ from the oracle signer
+  const auditorKeyStr = process.env.AUDITOR_PUBKEY;
+  if (auditorKeyStr) {
+    try {
+      const auditorKey = new anchor.web3.PublicKey(auditorKeyStr);
+      const tx = await program.methods
+        .addAuditor(auditorKey)
+        .accounts({ authority: wallet.publicKey, orgConfig: orgConfigPda })
+        .rpc();
+      console.log("Auditor added:", auditorKey.toBase58(), "Tx:", tx);
+    } catch (e: any) {
+      console.log("Add auditor skipped:", e.message || e);
+    }
+  } else {
+    console.log("AUDITOR_PUBKEY not provided; skipping auditor registration.");
+  }
+
+  // 4) Register a vendor (matches what OCR likely returns)
   const vendorName = process.env.VENDOR_NAME || "Unknown Vendor";
   const [vendorPda] = anchor.web3.PublicKey.findProgramAddressSync(
-    [Buffer.from("vendor"), orgConfigPda.toBuffer(), Buffer.from(vendorName)],
+    [Buffer.from("vendor"), orgConfigPda.toBuffer(), vendorSeed(vendorName)],
     program.programId
   );
   try {
@@ -0,0 +1,127 @@
+import * as anchor from "@coral-xyz/anchor";
+import { createHash } from "crypto";
+
+// Vendor PDAs are seeded by sha256 of the normalized name, matching `vendor_seed` on-chain
+const vendorSeed = (name: string) =>
+  createHash("sha256")
+    .update(name.trim().split(/\s+/).join(" ").replace(/[A-Z]/g, (c) => c.toLowerCase()))
+    .digest();
+
+// Grows an org created before auditors to the current layout, moves vendors registered
+// under the old raw-name seed to the hashed seed, then points their unpaid invoices at the
+// new accounts. Must be run with the org authority wallet.
+async function main() {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = (anchor.workspace as any).InvoiceClaim as any;
+  const wallet = provider.wallet as any;
+
+  const [orgConfigPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("org_config"), wallet.publicKey.toBuffer()],
+    program.programId
+  );
+
+  // Nothing else loads the org until it is in the current layout. The old one is
+  // discriminator + 4 pubkeys + 4 u64 + u16 + bool + u64 + 2 u8
+  const LEGACY_ORG_CONFIG_SIZE = 8 + 4 * 32 + 4 * 8 + 2 + 1 + 8 + 2;
+  const orgInfo = await provider.connection.getAccountInfo(orgConfigPda);
+  if (orgInfo?.data.length === LEGACY_ORG_CONFIG_SIZE) {
+    const tx = await program.methods
+      .migrateOrgConfig()
+      .accounts({
+        orgConfig: orgConfigPda,
+        authority: wallet.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+    console.log("Migrated org config:", orgConfigPda.toBase58(), "Tx:", tx);
+  }
+
+  // Legacy vendors predate the payment counters and don't decode as VendorAccount, so read
+  // the fields both layouts share: org after the 8-byte discriminator, then vendor_name
+  const vendors = await provider.connection.getProgramAccounts(program.programId, {
+    filters: [
+      { memcmp: program.coder.accounts.memcmp("vendorAccount") },
+      { memcmp: { offset: 8, bytes: orgConfigPda.toBase58() } },
+    ],
+  });
+
+  let migrated = 0;
+  for (const { pubkey: publicKey, account } of vendors) {
+    const nameLength = account.data.readUInt32LE(40);
+    const name = account.data.subarray(44, 44 + nameLength).toString("utf8");
+    const nameBytes = Buffer.from(name);
+    // Names over 32 bytes could never have had a legacy PDA
+    if (nameBytes.length > 32) continue;
+
+    let legacyPda: anchor.web3.PublicKey;
+    try {
+      [legacyPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("vendor"), orgConfigPda.toBuffer(), nameBytes],
+        program.programId
+      );
+    } catch {
+      continue;
+    }
+    if (!legacyPda.equals(publicKey)) continue;
+
+    const [vendorPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vendor"), orgConfigPda.toBuffer(), vendorSeed(name)],
+      program.programId
+    );
+
+    try {
+      const tx = await program.methods
+        .migrateVendor(name)
+        .accounts({
+          legacyVendorAccount: legacyPda,
+          vendorAccount: vendorPda,
+          orgConfig: orgConfigPda,
+          authority: wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+      console.log("Migrated vendor:", name, "->", vendorPda.toBase58(), "Tx:", tx);
+      migrated += 1;
+    } catch (e: any) {
+      console.log("Migrate vendor failed:", name, e.message || e);
+    }
+  }
+
+  // Settling or paying an invoice loads its vendor, which is gone from the legacy address.
+  // Also catches invoices of vendors migrated by an earlier run; org follows the authority
+  const invoices = await program.account.invoiceAccount.all([
+    { memcmp: { offset: 40, bytes: orgConfigPda.toBase58() } },
+  ]);
+  let repointed = 0;
+  for (const { publicKey, account } of invoices) {
+    if (account.status.paid || account.status.cancelled) continue;
+    const [vendorPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vendor"), orgConfigPda.toBuffer(), vendorSeed(account.vendorName)],
+      program.programId
+    );
+    if (account.vendor.equals(vendorPda)) continue;
+    try {
+      const tx = await program.methods
+        .migrateInvoiceVendor()
+        .accounts({
+          invoiceAccount: publicKey,
+          vendorAccount: vendorPda,
+          orgConfig: orgConfigPda,
+          authority: wallet.publicKey,
+        })
+        .rpc();
+      console.log("Invoice", account.invoiceId.toString(), "->", vendorPda.toBase58(), "Tx:", tx);
+      repointed += 1;
+    } catch (e: any) {
+      console.log("Repoint invoice failed:", publicKey.toBase58(), e.message || e);
+    }
+  }
+
+  console.log(`Migration complete. ${migrated} of ${vendors.length} vendor(s) migrated, ${repointed} invoice(s) repointed.`);
+}
+
+main().catch((e) => {
+  console.error(e);
+  process.exit(1);
+});
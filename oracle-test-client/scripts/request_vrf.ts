@@ -11,10 +11,20 @@ async function main() {
     [Buffer.from("org_config"), wallet.publicKey.toBuffer()],
     program.programId
   );
+
+  // Defaults to the org's most recently issued invoice id
+  let invoiceId: anchor.BN;
+  if (process.env.INVOICE_ID) {
+    invoiceId = new anchor.BN(process.env.INVOICE_ID);
+  } else {
+    const org = await program.account.orgConfig.fetch(orgConfigPda);
+    invoiceId = org.invoiceCounter.subn(1);
+  }
   const [invoicePda] = anchor.web3.PublicKey.findProgramAddressSync(
-    [Buffer.from("invoice"), wallet.publicKey.toBuffer()],
+    [Buffer.from("invoice"), orgConfigPda.toBuffer(), invoiceId.toArrayLike(Buffer, "le", 8)],
     program.programId
   );
+  console.log("Invoice ID:", invoiceId.toString());
 
   const queueStr = process.env.QUEUE_PUBKEY;
   if (!queueStr) {
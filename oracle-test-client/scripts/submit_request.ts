@@ -1,4 +1,5 @@
 import * as anchor from "@coral-xyz/anchor";
+import { getMint } from "@solana/spl-token";
 
 async function main() {
   const provider = anchor.AnchorProvider.env();
@@ -7,28 +8,40 @@ async function main() {
 
   const wallet = provider.wallet as any;
   const ipfsHash = process.env.IPFS_HASH || "bafkreibjntqp7vaggmvtlgs2sptrjhiwywmrqwlcdbdoi2ub2medwdqomm";
-  const amount = new anchor.BN(parseInt(process.env.REQUEST_AMOUNT || "100", 10));
 
+  // Org the invoice is submitted to; defaults to an org owned by this wallet
+  const orgAuthority = new anchor.web3.PublicKey(process.env.ORG_AUTHORITY_PUBKEY || wallet.publicKey.toBase58());
+  const [orgConfigPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("org_config"), orgAuthority.toBuffer()],
+    program.programId
+  );
+
+  // The request takes the org's next invoice id
+  const org = await program.account.orgConfig.fetch(orgConfigPda);
+
+  // REQUEST_AMOUNT is in whole tokens, e.g. "40.50"; the program takes base units of the org's mint
+  const { decimals } = await getMint(provider.connection, org.mint);
+  const [whole, fraction = ""] = (process.env.REQUEST_AMOUNT || "100").split(".");
+  if (fraction.length > decimals) throw new Error(`REQUEST_AMOUNT has more than ${decimals} decimals`);
+  const amount = new anchor.BN(whole + fraction.padEnd(decimals, "0"));
+  const invoiceId: anchor.BN = org.invoiceCounter;
   const [requestPda] = anchor.web3.PublicKey.findProgramAddressSync(
-    [Buffer.from("request"), wallet.publicKey.toBuffer()],
+    [Buffer.from("request"), orgConfigPda.toBuffer(), invoiceId.toArrayLike(Buffer, "le", 8)],
     program.programId
   );
 
   console.log("Submitting invoice extraction request...");
   console.log("Authority:", wallet.publicKey.toBase58());
+  console.log("Org config:", orgConfigPda.toBase58());
+  console.log("Invoice ID:", invoiceId.toString());
   console.log("Request PDA:", requestPda.toBase58());
   console.log("IPFS Hash:", ipfsHash);
   console.log("Amount:", amount.toString());
 
-  try {
-    const existing = await program.account.invoiceRequest.fetch(requestPda);
-    console.log("Request already exists. Status:", existing.status);
-    return;
-  } catch {}
-
   const tx = await program.methods
     .requestInvoiceExtraction(ipfsHash, amount)
     .accounts({
+      orgConfig: orgConfigPda,
       invoiceRequest: requestPda,
       authority: wallet.publicKey,
       systemProgram: anchor.web3.SystemProgram.programId,
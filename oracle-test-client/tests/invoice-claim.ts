@@ -1,5 +1,18 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
+import {
+  createAccount,
+  createMint,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getMint,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+  transfer,
+  TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { createHash } from "crypto";
 import { InvoiceClaim } from "../target/types/invoice_claim";
 
 describe("invoice-claim", () => {
@@ -8,32 +21,47 @@ describe("invoice-claim", () => {
 
   const program = anchor.workspace.InvoiceClaim as Program<InvoiceClaim>;
 
+  // OrgConfig PDA is derived from the org authority. Many setups use the same wallet.
+  const [orgConfigPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("org_config"), provider.wallet.publicKey.toBuffer()],
+    program.programId
+  );
+
+  // Requests and invoices are numbered by the org's invoice_counter
+  let invoiceId: anchor.BN;
+  const idSeed = (id: anchor.BN) => id.toArrayLike(Buffer, "le", 8);
+  const requestPdaFor = (id: anchor.BN) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("request"), orgConfigPda.toBuffer(), idSeed(id)],
+      program.programId
+    )[0];
+  const invoicePdaFor = (id: anchor.BN) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("invoice"), orgConfigPda.toBuffer(), idSeed(id)],
+      program.programId
+    )[0];
+
   it("Submits invoice for extraction", async () => {
     const ipfsHash = "bafkreibjntqp7vaggmvtlgs2sptrjhiwywmrqwlcdbdoi2ub2medwdqomm";
 
-    const [requestPda] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("request"), provider.wallet.publicKey.toBuffer()],
-        program.programId
-    );
+    const org = await program.account.orgConfig.fetch(orgConfigPda);
+    invoiceId = org.invoiceCounter;
+    const requestPda = requestPdaFor(invoiceId);
+
+    // Claimed total of 100 tokens, in base units of the org's mint
+    const { decimals } = await getMint(provider.connection, org.mint);
+    const amount = new anchor.BN(100).mul(new anchor.BN(10).pow(new anchor.BN(decimals)));
 
     console.log("\nSubmitting invoice extraction request...");
     console.log("Authority:", provider.wallet.publicKey.toString());
+    console.log("Invoice ID:", invoiceId.toString());
     console.log("Request PDA:", requestPda.toString());
     console.log("IPFS Hash:", ipfsHash);
 
-    try {
-      const existingAccount = await program.account.invoiceRequest.fetch(requestPda);
-      console.log("\nRequest already exists!");
-      console.log("Status:", existingAccount.status);
-      console.log("Skipping submission...");
-      return;
-    } catch (e) {
-      console.log("Creating new request...");
-    }
-
     const tx = await program.methods
-        .requestInvoiceExtraction(ipfsHash,new anchor.BN(100))
+        .requestInvoiceExtraction(ipfsHash, amount)
         .accounts({
+          orgConfig: orgConfigPda,
           invoiceRequest: requestPda,
           authority: provider.wallet.publicKey,
           systemProgram: anchor.web3.SystemProgram.programId,
@@ -49,10 +77,7 @@ describe("invoice-claim", () => {
   });
 
   it("Check invoice data after oracle processes", async () => {
-    const [invoicePda] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("invoice"), provider.wallet.publicKey.toBuffer()],
-        program.programId
-    );
+    const invoicePda = invoicePdaFor(invoiceId);
 
     console.log("\nWaiting 10 seconds for oracle to process...");
     await new Promise(resolve => setTimeout(resolve, 10000));
@@ -109,15 +134,9 @@ describe("invoice-claim", () => {
 
 
   it("View complete invoice lifecycle", async () => {
-    const [requestPda] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("request"), provider.wallet.publicKey.toBuffer()],
-        program.programId
-    );
+    const requestPda = requestPdaFor(invoiceId);
 
-    const [invoicePda] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("invoice"), provider.wallet.publicKey.toBuffer()],
-        program.programId
-    );
+    const invoicePda = invoicePdaFor(invoiceId);
 
     console.log("\n📊 Complete Invoice Lifecycle:");
     console.log("================================");
@@ -147,10 +166,7 @@ describe("invoice-claim", () => {
   });
 
   it("Process invoice payment (move to escrow)", async () => {
-    const [invoicePda] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("invoice"), provider.wallet.publicKey.toBuffer()],
-      program.programId
-    );
+    const invoicePda = invoicePdaFor(invoiceId);
 
     try {
       const before = await program.account.invoiceAccount.fetch(invoicePda);
@@ -164,6 +180,7 @@ describe("invoice-claim", () => {
         .processInvoicePayment()
         .accounts({
           invoiceAccount: invoicePda,
+          orgConfig: orgConfigPda,
           authority: provider.wallet.publicKey,
         })
         .rpc();
@@ -172,46 +189,62 @@ describe("invoice-claim", () => {
 
       const after = await program.account.invoiceAccount.fetch(invoicePda);
       console.log("New status:", after.status);
+
+      // Payments count against the org's daily cap
+      const headroom = await program.methods
+        .getDailyHeadroom()
+        .accounts({ orgConfig: orgConfigPda })
+        .view();
+      console.log("Daily cap headroom left:", headroom.toString());
     } catch (e) {
       console.log("Payment attempt failed:", (e as any).message);
     }
   });
 
   it("Approve audit if pending (manual review)", async () => {
-    const [invoicePda] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("invoice"), provider.wallet.publicKey.toBuffer()],
-      program.programId
-    );
+    const invoicePda = invoicePdaFor(invoiceId);
 
-    // OrgConfig PDA is derived from the org authority. Many setups use the same wallet.
-    const [orgConfigPda] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("org_config"), provider.wallet.publicKey.toBuffer()],
-      program.programId
-    );
 
     try {
       const invoice = await program.account.invoiceAccount.fetch(invoicePda);
       console.log("\nCurrent invoice status:", invoice.status);
 
+      const [auditPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("audit"), invoicePda.toBuffer()],
+        program.programId
+      );
+
       // Only approve if VRF selected this invoice for audit
       if (invoice.status === 1 /* AuditPending enum index */) {
         console.log("Audit pending → attempting approval via auditDecide(true)...");
         try {
+          // Only registered auditors may decide; fails harmlessly if already added
+          // or if this wallet is still the oracle signer
+          await program.methods
+            .addAuditor(provider.wallet.publicKey)
+            .accounts({ authority: provider.wallet.publicKey, orgConfig: orgConfigPda })
+            .rpc()
+            .catch((e) => console.log("addAuditor skipped:", (e as any).message));
+
           const tx = await program.methods
-            .auditDecide(true)
+            .auditDecide(true, { verified: {} }, invoice.ipfsHash)
             .accounts({
               reviewer: provider.wallet.publicKey,
               orgConfig: orgConfigPda,
               invoiceAccount: invoicePda,
+              auditRecord: auditPda,
             })
             .rpc();
           console.log("\n✅ Audit approved. Tx:", tx);
 
           const after = await program.account.invoiceAccount.fetch(invoicePda);
           console.log("New status:", after.status);
+
+          const record = await program.account.auditRecord.fetch(auditPda);
+          console.log("Audit record: reason", record.reason, "evidence", record.evidenceHash);
         } catch (e) {
           console.log("\nCould not approve audit (likely unauthorized or wrong OrgConfig PDA):", (e as any).message);
-          console.log("Hint: org_config PDA seed must match the authority used during org_init, and reviewer must be org authority or oracle_signer.");
+          console.log("Hint: org_config PDA seed must match the authority used during org_init, and reviewer must be a registered auditor other than oracle_signer.");
         }
       } else {
         console.log("No audit approval needed. Status is not AuditPending.");
@@ -224,10 +257,7 @@ describe("invoice-claim", () => {
   // Tests rely on VRF-driven state transitions.
 
   it("Payment fails unless ReadyForPayment", async () => {
-    const [invoicePda] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("invoice"), provider.wallet.publicKey.toBuffer()],
-      program.programId
-    );
+    const invoicePda = invoicePdaFor(invoiceId);
 
     try {
       const invoice = await program.account.invoiceAccount.fetch(invoicePda);
@@ -244,6 +274,7 @@ describe("invoice-claim", () => {
           .processInvoicePayment()
           .accounts({
             invoiceAccount: invoicePda,
+            orgConfig: orgConfigPda,
             authority: provider.wallet.publicKey,
           })
           .rpc();
@@ -257,15 +288,9 @@ describe("invoice-claim", () => {
   });
 
   it("Close invoice accounts and reclaim rent", async () => {
-    const [requestPda] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("request"), provider.wallet.publicKey.toBuffer()],
-        program.programId
-    );
+    const requestPda = requestPdaFor(invoiceId);
 
-    const [invoicePda] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("invoice"), provider.wallet.publicKey.toBuffer()],
-        program.programId
-    );
+    const invoicePda = invoicePdaFor(invoiceId);
 
     console.log("\nClosing accounts and reclaiming rent...");
 
@@ -280,6 +305,8 @@ describe("invoice-claim", () => {
             .closeInvoice()
             .accounts({
               invoiceAccount: invoicePda,
+              orgConfig: orgConfigPda,
+              leftoverAta: null,
               authority: provider.wallet.publicKey,
             })
             .rpc();
@@ -319,3 +346,507 @@ describe("invoice-claim", () => {
     }
   });
 });
+
+describe("invoice-claim cancellation and refunds", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.InvoiceClaim as Program<InvoiceClaim>;
+  const connection = provider.connection;
+  const systemProgram = anchor.web3.SystemProgram.programId;
+
+  // A throwaway org whose authority is also its oracle signer, so every step can be
+  // driven from here; the provider wallet is its auditor
+  const authority = anchor.web3.Keypair.generate();
+  const vendorName = "Lifecycle Test Vendor";
+  const DECIMALS = 6;
+  // ephemeral_vrf_sdk::consts::DEFAULT_QUEUE
+  const VRF_DEFAULT_QUEUE = new anchor.web3.PublicKey("Cuj97ggrhhidhbu39TijNVqE74xvKJ69gDervRUXAxGh");
+  const amount = new anchor.BN(40 * 10 ** DECIMALS);
+
+  const pda = (...seeds: (string | Buffer | anchor.web3.PublicKey)[]) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      seeds.map((s) => (typeof s === "string" ? Buffer.from(s) : s instanceof Buffer ? s : s.toBuffer())),
+      program.programId
+    )[0];
+  const sha256 = (...parts: Buffer[]) => createHash("sha256").update(Buffer.concat(parts)).digest();
+  // Mirrors `vendor_seed` and `invoice_fingerprint` on-chain
+  const vendorSeed = (name: string) =>
+    sha256(Buffer.from(name.trim().split(/\s+/).join(" ").replace(/[A-Z]/g, (c) => c.toLowerCase())));
+  const fingerprint = (vendor: anchor.web3.PublicKey, invoiceNumber: string, amount: anchor.BN) =>
+    sha256(
+      vendor.toBuffer(),
+      Buffer.from(invoiceNumber.replace(/[^A-Za-z0-9]/g, "").toUpperCase()),
+      amount.toArrayLike(Buffer, "le", 8)
+    );
+
+  const orgConfigPda = pda("org_config", authority.publicKey);
+  const vendorPda = pda("vendor", orgConfigPda, vendorSeed(vendorName));
+  const idSeed = (id: anchor.BN) => id.toArrayLike(Buffer, "le", 8);
+  const requestPdaFor = (id: anchor.BN) => pda("request", orgConfigPda, idSeed(id));
+  const invoicePdaFor = (id: anchor.BN) => pda("invoice", orgConfigPda, idSeed(id));
+  const escrowFor = (invoicePda: anchor.web3.PublicKey) => {
+    const escrowAuthority = pda("escrow_auth", invoicePda);
+    return { escrowAuthority, escrowAta: getAssociatedTokenAddressSync(mint, escrowAuthority, true) };
+  };
+
+  let mint: anchor.web3.PublicKey;
+  let authorityAta: anchor.web3.PublicKey;
+
+  const expectError = async (tx: Promise<unknown>, code: string) => {
+    try {
+      await tx;
+    } catch (e: any) {
+      expect(e.error?.errorCode?.code, e.message).to.equal(code);
+      return;
+    }
+    expect.fail(`expected ${code}`);
+  };
+
+  // Takes the org's next invoice id for `ipfsHash`
+  const submit = async (ipfsHash: string) => {
+    const id = (await program.account.orgConfig.fetch(orgConfigPda)).invoiceCounter;
+    await program.methods
+      .requestInvoiceExtraction(ipfsHash, amount)
+      .accounts({
+        orgConfig: orgConfigPda,
+        invoiceRequest: requestPdaFor(id),
+        authority: authority.publicKey,
+        systemProgram,
+      })
+      .signers([authority])
+      .rpc();
+    return id;
+  };
+
+  // What the oracle would submit for the document; confidence 0 sends it to audit
+  const extract = (id: anchor.BN, ipfsHash: string, invoiceNumber: string, extracted = amount) =>
+    program.methods
+      .processExtractionResult(
+        vendorName,
+        extracted,
+        new anchor.BN(Math.floor(Date.now() / 1000) + 30 * 86400),
+        invoiceNumber,
+        0
+      )
+      .accounts({
+        payer: authority.publicKey,
+        orgConfig: orgConfigPda,
+        vendorAccount: vendorPda,
+        invoiceRequest: requestPdaFor(id),
+        invoiceAccount: invoicePdaFor(id),
+        documentRegistry: pda("doc_hash", orgConfigPda, sha256(Buffer.from(ipfsHash))),
+        fingerprintRegistry: pda("fingerprint", orgConfigPda, fingerprint(vendorPda, invoiceNumber, extracted)),
+        systemProgram,
+      })
+      .signers([authority])
+      .rpc();
+
+  const approve = (id: anchor.BN) =>
+    program.methods
+      .auditDecide(true, { verified: {} }, "lifecycle-test")
+      .accounts({
+        reviewer: provider.wallet.publicKey,
+        orgConfig: orgConfigPda,
+        invoiceAccount: invoicePdaFor(id),
+        auditRecord: pda("audit", invoicePdaFor(id)),
+        systemProgram,
+      })
+      .rpc();
+
+  const closeInvoice = (id: anchor.BN, leftoverAta: anchor.web3.PublicKey | null = null) =>
+    program.methods
+      .closeInvoice()
+      .accounts({
+        invoiceAccount: invoicePdaFor(id),
+        orgConfig: orgConfigPda,
+        ...escrowFor(invoicePdaFor(id)),
+        leftoverAta,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        authority: authority.publicKey,
+      })
+      .signers([authority])
+      .rpc();
+
+  const cancelInvoice = (id: anchor.BN) =>
+    program.methods
+      .cancelInvoice()
+      .accounts({ invoiceAccount: invoicePdaFor(id), authority: authority.publicKey })
+      .signers([authority])
+      .rpc();
+
+  // Submitted, extracted and approved by the auditor
+  const ready = async (ipfsHash: string, invoiceNumber: string) => {
+    const id = await submit(ipfsHash);
+    await extract(id, ipfsHash, invoiceNumber);
+    await approve(id);
+    return id;
+  };
+
+  const updateOrg = (settings: {
+    perInvoiceCap?: anchor.BN;
+    dailyCap?: anchor.BN;
+    amountToleranceBps?: number;
+    minConfidenceBps?: number;
+  }) =>
+    program.methods
+      .updateOrgConfig({
+        oracleSigner: null,
+        perInvoiceCap: settings.perInvoiceCap ?? null,
+        dailyCap: settings.dailyCap ?? null,
+        paused: null,
+        amountToleranceBps: settings.amountToleranceBps ?? null,
+        minConfidenceBps: settings.minConfidenceBps ?? null,
+      })
+      .accounts({ authority: authority.publicKey, orgConfig: orgConfigPda })
+      .signers([authority])
+      .rpc();
+
+  // Funds the invoice's escrow from the authority's tokens; `accounts` swaps in bad ones
+  const fundEscrow = (id: anchor.BN, accounts: Partial<Record<string, anchor.web3.PublicKey>> = {}) => {
+    const invoicePda = invoicePdaFor(id);
+    return program.methods
+      .fundEscrow()
+      .accounts({
+        orgConfig: orgConfigPda,
+        invoiceAccount: invoicePda,
+        payer: authority.publicKey,
+        authority: authority.publicKey,
+        payerAta: authorityAta,
+        ...escrowFor(invoicePda),
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        ...accounts,
+      })
+      .signers([authority])
+      .rpc();
+  };
+
+  const invoiceStatus = async (id: anchor.BN) => (await program.account.invoiceAccount.fetch(invoicePdaFor(id))).status;
+  const tokenBalance = async (ata: anchor.web3.PublicKey) => Number((await getAccount(connection, ata)).amount);
+
+  before(async () => {
+    // Rent for the org, vendor, mint, requests, invoices and registries
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.transfer({
+          fromPubkey: provider.wallet.publicKey,
+          toPubkey: authority.publicKey,
+          lamports: anchor.web3.LAMPORTS_PER_SOL / 2,
+        })
+      )
+    );
+
+    mint = await createMint(connection, authority, authority.publicKey, null, DECIMALS);
+    authorityAta = (await getOrCreateAssociatedTokenAccount(connection, authority, mint, authority.publicKey)).address;
+    await mintTo(connection, authority, mint, authorityAta, authority, 1_000 * 10 ** DECIMALS);
+
+    await program.methods
+      .orgInit(authority.publicKey, mint, new anchor.BN(1_000 * 10 ** DECIMALS), new anchor.BN(10_000 * 10 ** DECIMALS), 0)
+      .accounts({ orgConfig: orgConfigPda, authority: authority.publicKey, systemProgram })
+      .signers([authority])
+      .rpc();
+
+    // Every invoice goes to audit, so the auditor decides when one is ReadyForPayment
+    await updateOrg({ minConfidenceBps: 10_000 });
+    await program.methods
+      .addAuditor(provider.wallet.publicKey)
+      .accounts({ authority: authority.publicKey, orgConfig: orgConfigPda })
+      .signers([authority])
+      .rpc();
+
+    await program.methods
+      .registerVendor(vendorName, anchor.web3.Keypair.generate().publicKey)
+      .accounts({ vendorAccount: vendorPda, orgConfig: orgConfigPda, authority: authority.publicKey, systemProgram })
+      .signers([authority])
+      .rpc();
+  });
+
+  let cancelledId: anchor.BN;
+  let refundedId: anchor.BN;
+
+  it("Cancels an unfunded invoice once", async () => {
+    cancelledId = await submit("bafkreilifecyclecancel");
+    await extract(cancelledId, "bafkreilifecyclecancel", "INV-100");
+    expect(await invoiceStatus(cancelledId)).to.deep.equal({ auditPending: {} });
+
+    await cancelInvoice(cancelledId);
+    expect(await invoiceStatus(cancelledId)).to.deep.equal({ cancelled: {} });
+
+    await expectError(cancelInvoice(cancelledId), "InvalidStatus");
+  });
+
+  it("Blocks cancelling or closing a funded invoice until the escrow is refunded", async () => {
+    refundedId = await submit("bafkreilifecyclerefund");
+    await extract(refundedId, "bafkreilifecyclerefund", "INV-101");
+    await approve(refundedId);
+    expect(await invoiceStatus(refundedId)).to.deep.equal({ readyForPayment: {} });
+
+    const invoicePda = invoicePdaFor(refundedId);
+    const { escrowAuthority, escrowAta } = escrowFor(invoicePda);
+    await getOrCreateAssociatedTokenAccount(connection, authority, mint, escrowAuthority, true);
+    const before = await tokenBalance(authorityAta);
+    const dailySpent = async () => (await program.account.orgConfig.fetch(orgConfigPda)).dailySpent.toNumber();
+    const spentBefore = await dailySpent();
+
+    await fundEscrow(refundedId);
+    expect(await invoiceStatus(refundedId)).to.deep.equal({ inEscrow: {} });
+    expect(await tokenBalance(escrowAta)).to.equal(amount.toNumber());
+    expect(await dailySpent()).to.equal(spentBefore + amount.toNumber());
+
+    await expectError(cancelInvoice(refundedId), "EscrowActive");
+    await expectError(closeInvoice(refundedId), "EscrowActive");
+
+    await program.methods
+      .refundEscrow()
+      .accounts({
+        orgConfig: orgConfigPda,
+        invoiceAccount: invoicePda,
+        escrowAuthority,
+        escrowAta,
+        funderAta: authorityAta,
+        mint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        authority: authority.publicKey,
+      })
+      .signers([authority])
+      .rpc();
+    expect(await invoiceStatus(refundedId)).to.deep.equal({ cancelled: {} });
+    expect(await tokenBalance(escrowAta)).to.equal(0);
+    expect(await tokenBalance(authorityAta)).to.equal(before);
+    // Refunded the same day, so the amount no longer counts against the daily cap
+    expect(await dailySpent()).to.equal(spentBefore);
+  });
+
+  it("Returns tokens sent to a refunded escrow when the invoice is closed", async () => {
+    const { escrowAta } = escrowFor(invoicePdaFor(refundedId));
+    await transfer(connection, authority, authorityAta, escrowAta, authority, 1);
+    const before = await tokenBalance(authorityAta);
+
+    // The leftover needs somewhere to go
+    await expectError(closeInvoice(refundedId), "EscrowNotEmpty");
+
+    await closeInvoice(refundedId, authorityAta);
+    expect(await tokenBalance(authorityAta)).to.equal(before + 1);
+    expect(await connection.getAccountInfo(escrowAta)).to.be.null;
+    expect(await program.account.invoiceAccount.fetchNullable(invoicePdaFor(refundedId))).to.be.null;
+
+    // An invoice that never had an escrow closes normally
+    await closeInvoice(cancelledId);
+    expect(await program.account.invoiceAccount.fetchNullable(invoicePdaFor(cancelledId))).to.be.null;
+  });
+
+  it("Rejects a pending request with the oracle's reason", async () => {
+    const id = await submit("bafkreilifecyclereject");
+    const reject = (oracle: anchor.web3.Keypair | null) =>
+      program.methods
+        .rejectExtraction({ unknownVendor: {} })
+        .accounts({
+          oracle: oracle ? oracle.publicKey : provider.wallet.publicKey,
+          orgConfig: orgConfigPda,
+          invoiceRequest: requestPdaFor(id),
+        })
+        .signers(oracle ? [oracle] : [])
+        .rpc();
+
+    // Only the org's oracle signer may reject
+    await expectError(reject(null), "Unauthorized");
+
+    await reject(authority);
+    const request = await program.account.invoiceRequest.fetch(requestPdaFor(id));
+    expect(request.status).to.deep.equal({ failed: {} });
+    expect(request.rejectReason).to.deep.equal({ unknownVendor: {} });
+
+    await expectError(reject(authority), "InvalidStatus");
+
+    // A late extraction result cannot turn the rejected request into an invoice
+    await expectError(extract(id, "bafkreilifecyclereject", "INV-200"), "InvalidStatus");
+    expect(await program.account.invoiceAccount.fetchNullable(invoicePdaFor(id))).to.be.null;
+  });
+
+  it("Refuses a document that already became an invoice", async () => {
+    // Same CID as the cancelled invoice; its document registry stays claimed after closing
+    const id = await submit("bafkreilifecyclecancel");
+    await expectError(extract(id, "bafkreilifecyclecancel", "INV-300"), "DuplicateInvoice");
+
+    const request = await program.account.invoiceRequest.fetch(requestPdaFor(id));
+    expect(request.status).to.deep.equal({ pending: {} });
+    expect(await program.account.invoiceAccount.fetchNullable(invoicePdaFor(id))).to.be.null;
+  });
+
+  it("Refuses the same invoice number and amount from the same vendor", async () => {
+    const id = await submit("bafkreilifecyclerescan");
+    // "inv 101" normalizes to the refunded invoice's "INV-101"
+    await expectError(extract(id, "bafkreilifecyclerescan", "inv 101"), "DuplicateInvoice");
+
+    // A different amount is a different invoice
+    await extract(id, "bafkreilifecyclerescan", "inv 101", amount.addn(1));
+    expect(await invoiceStatus(id)).to.deep.equal({ auditPending: {} });
+  });
+
+  it("Refuses to migrate an org that is already in the current layout", async () => {
+    await expectError(
+      program.methods
+        .migrateOrgConfig()
+        .accounts({ orgConfig: orgConfigPda, authority: authority.publicKey, systemProgram })
+        .signers([authority])
+        .rpc(),
+      "AccountDidNotDeserialize"
+    );
+  });
+
+  it("Refuses to escrow more than the daily cap allows", async () => {
+    const first = await ready("bafkreilifecycledaily1", "INV-500");
+    const second = await ready("bafkreilifecycledaily2", "INV-501");
+    for (const id of [first, second]) {
+      const { escrowAuthority } = escrowFor(invoicePdaFor(id));
+      await getOrCreateAssociatedTokenAccount(connection, authority, mint, escrowAuthority, true);
+    }
+
+    // Room for exactly one more invoice today
+    const org = await program.account.orgConfig.fetch(orgConfigPda);
+    await updateOrg({ perInvoiceCap: amount, dailyCap: org.dailySpent.add(amount) });
+    try {
+      await fundEscrow(first);
+      await expectError(fundEscrow(second), "DailyCapExceeded");
+      expect(await invoiceStatus(second)).to.deep.equal({ readyForPayment: {} });
+    } finally {
+      await updateOrg({ perInvoiceCap: org.perInvoiceCap, dailyCap: org.dailyCap });
+    }
+  });
+
+  it("Refuses escrow token accounts of the wrong owner or mint", async () => {
+    const id = await ready("bafkreilifecycleatas", "INV-600");
+    const { escrowAuthority } = escrowFor(invoicePdaFor(id));
+    await getOrCreateAssociatedTokenAccount(connection, authority, mint, escrowAuthority, true);
+
+    // The authority's own account as the escrow, and a stranger's as the source
+    await expectError(fundEscrow(id, { escrowAta: authorityAta }), "ConstraintTokenOwner");
+    const stranger = anchor.web3.Keypair.generate().publicKey;
+    const strangerAta = (await getOrCreateAssociatedTokenAccount(connection, authority, mint, stranger)).address;
+    await expectError(fundEscrow(id, { payerAta: strangerAta }), "ConstraintTokenOwner");
+
+    // Owned by the escrow authority but not its associated account
+    const looseAccount = await createAccount(connection, authority, mint, escrowAuthority, anchor.web3.Keypair.generate());
+    await expectError(fundEscrow(id, { escrowAta: looseAccount }), "ConstraintAssociated");
+
+    // Consistent accounts, but in a mint the org does not pay in
+    const otherMint = await createMint(connection, authority, authority.publicKey, null, DECIMALS);
+    const otherPayerAta = (await getOrCreateAssociatedTokenAccount(connection, authority, otherMint, authority.publicKey)).address;
+    await mintTo(connection, authority, otherMint, otherPayerAta, authority, amount.toNumber());
+    const otherEscrowAta = (await getOrCreateAssociatedTokenAccount(connection, authority, otherMint, escrowAuthority, true))
+      .address;
+    await expectError(
+      fundEscrow(id, { mint: otherMint, payerAta: otherPayerAta, escrowAta: otherEscrowAta }),
+      "WrongMint"
+    );
+
+    expect(await invoiceStatus(id)).to.deep.equal({ readyForPayment: {} });
+  });
+
+  it("Keeps the oracle signer out of audit decisions", async () => {
+    const id = await submit("bafkreilifecycleaudit");
+    await extract(id, "bafkreilifecycleaudit", "INV-700");
+
+    await expectError(
+      program.methods
+        .auditDecide(true, { verified: {} }, "lifecycle-test")
+        .accounts({
+          reviewer: authority.publicKey,
+          orgConfig: orgConfigPda,
+          invoiceAccount: invoicePdaFor(id),
+          auditRecord: pda("audit", invoicePdaFor(id)),
+          systemProgram,
+        })
+        .signers([authority])
+        .rpc(),
+      "OracleCannotAudit"
+    );
+    await expectError(
+      program.methods
+        .addAuditor(authority.publicKey)
+        .accounts({ authority: authority.publicKey, orgConfig: orgConfigPda })
+        .signers([authority])
+        .rpc(),
+      "OracleCannotAudit"
+    );
+    expect(await invoiceStatus(id)).to.deep.equal({ auditPending: {} });
+  });
+
+  it("Refuses another org's config for an invoice's escrow or audit draw", async () => {
+    const id = await submit("bafkreilifecycleorgs");
+    await extract(id, "bafkreilifecycleorgs", "INV-800");
+
+    // A second org with the same mint; only the invoice's own org may act on it
+    const otherAuthority = anchor.web3.Keypair.generate();
+    const otherOrgConfig = pda("org_config", otherAuthority.publicKey);
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.transfer({
+          fromPubkey: provider.wallet.publicKey,
+          toPubkey: otherAuthority.publicKey,
+          lamports: anchor.web3.LAMPORTS_PER_SOL / 20,
+        })
+      )
+    );
+    await program.methods
+      .orgInit(otherAuthority.publicKey, mint, new anchor.BN(1_000 * 10 ** DECIMALS), new anchor.BN(10_000 * 10 ** DECIMALS), 0)
+      .accounts({ orgConfig: otherOrgConfig, authority: otherAuthority.publicKey, systemProgram })
+      .signers([otherAuthority])
+      .rpc();
+
+    await expectError(fundEscrow(id, { orgConfig: otherOrgConfig }), "WrongOrg");
+    await expectError(
+      program.methods
+        .requestInvoiceAuditVrf(0)
+        .accounts({
+          payer: provider.wallet.publicKey,
+          orgConfig: otherOrgConfig,
+          invoiceAccount: invoicePdaFor(id),
+          oracleQueue: VRF_DEFAULT_QUEUE,
+        })
+        .rpc(),
+      "WrongOrg"
+    );
+  });
+
+  it("Sends extractions outside the claimed amount's tolerance to audit", async () => {
+    // 1% tolerance, and confidence no longer forces every invoice to audit
+    await updateOrg({ amountToleranceBps: 100, minConfidenceBps: 0 });
+    try {
+      const within = await submit("bafkreilifecycletolerance1");
+      await extract(within, "bafkreilifecycletolerance1", "INV-900", amount.add(amount.divn(200)));
+      expect(await invoiceStatus(within)).to.deep.equal({ validated: {} });
+
+      const outside = await submit("bafkreilifecycletolerance2");
+      await extract(outside, "bafkreilifecycletolerance2", "INV-901", amount.add(amount.divn(50)));
+      const invoice = await program.account.invoiceAccount.fetch(invoicePdaFor(outside));
+      expect(invoice.status).to.deep.equal({ auditPending: {} });
+      expect(invoice.claimedAmount.toString()).to.equal(amount.toString());
+      expect(invoice.amount.toString()).to.equal(amount.add(amount.divn(50)).toString());
+    } finally {
+      await updateOrg({ amountToleranceBps: 0, minConfidenceBps: 10_000 });
+    }
+  });
+
+  it("Only repoints invoices left on a legacy vendor address", async () => {
+    const id = await submit("bafkreilifecyclemigrate");
+    await extract(id, "bafkreilifecyclemigrate", "INV-400");
+    const repoint = (signer: anchor.web3.Keypair) =>
+      program.methods
+        .migrateInvoiceVendor()
+        .accounts({
+          invoiceAccount: invoicePdaFor(id),
+          vendorAccount: vendorPda,
+          orgConfig: orgConfigPda,
+          authority: signer.publicKey,
+        })
+        .signers([signer])
+        .rpc();
+
+    await expectError(repoint(anchor.web3.Keypair.generate()), "Unauthorized");
+    // Extracted against the hashed vendor PDA, so there is nothing to migrate
+    await expectError(repoint(authority), "InvalidVendor");
+    expect((await program.account.invoiceAccount.fetch(invoicePdaFor(id))).vendor).to.deep.equal(vendorPda);
+  });
+});